@@ -1,6 +1,6 @@
 use criterion::{self, criterion_group, Criterion};
 use pretty_assertions::assert_eq;
-use quick_xml::escape::{escape, unescape};
+use quick_xml::escape::{escape, escape_with, unescape, unescape_to};
 use quick_xml::events::Event;
 use quick_xml::name::QName;
 use quick_xml::Reader;
@@ -246,6 +246,31 @@ fn one_event(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("Start with long name", |b| {
+        let src = format!(
+            r#"<{}{}>"#,
+            "element".repeat(512 / 7),
+            r#" attr="value""#.repeat(16)
+        )
+        .into_bytes();
+        let mut buf = Vec::with_capacity(1024);
+        b.iter(|| {
+            let mut r = Reader::from_reader(src.as_ref());
+            let mut nbtxt = criterion::black_box(0);
+            r.check_end_names(false)
+                .check_comments(false)
+                .trim_text(true);
+            match r.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) => nbtxt += e.name().as_ref().len(),
+                something_else => panic!("Did not expect {:?}", something_else),
+            };
+
+            buf.clear();
+
+            assert_eq!(nbtxt, 511);
+        })
+    });
+
     group.bench_function("Comment", |b| {
         let src = format!(r#"<!-- hello "{}" -->"#, "world".repeat(512 / 5)).into_bytes();
         let mut buf = Vec::with_capacity(1024);
@@ -364,6 +389,56 @@ fn attributes(c: &mut Criterion) {
             assert_eq!(count, 150);
         })
     });
+
+    group.bench_function("unescape_and_decode_value", |b| {
+        b.iter(|| {
+            let mut r = Reader::from_reader(PLAYERS);
+            r.check_end_names(false).check_comments(false);
+            let mut count = criterion::black_box(0);
+            let mut buf = Vec::new();
+            loop {
+                match r.read_event_into(&mut buf) {
+                    Ok(Event::Empty(e)) => {
+                        for attr in e.attributes() {
+                            let value = attr.unwrap().unescape_and_decode_value(&r).unwrap();
+                            count += value.len();
+                        }
+                    }
+                    Ok(Event::Eof) => break,
+                    _ => (),
+                }
+                buf.clear();
+            }
+            assert_eq!(count, 3320);
+        })
+    });
+
+    group.bench_function("unescape_value_into", |b| {
+        b.iter(|| {
+            let mut r = Reader::from_reader(PLAYERS);
+            r.check_end_names(false).check_comments(false);
+            let mut count = criterion::black_box(0);
+            let mut buf = Vec::new();
+            let mut value = String::new();
+            loop {
+                match r.read_event_into(&mut buf) {
+                    Ok(Event::Empty(e)) => {
+                        for attr in e.attributes() {
+                            value.clear();
+                            attr.unwrap()
+                                .unescape_value_into(&mut value, r.decoder())
+                                .unwrap();
+                            count += value.len();
+                        }
+                    }
+                    Ok(Event::Eof) => break,
+                    _ => (),
+                }
+                buf.clear();
+            }
+            assert_eq!(count, 3320);
+        })
+    });
     group.finish();
 }
 
@@ -410,6 +485,18 @@ purus. Consequat id porta nibh venenatis cras sed felis.";
             criterion::black_box(escape(lorem_ipsum_with_escape_chars));
         })
     });
+
+    group.bench_function("escape_with_custom_chars_long", |b| {
+        b.iter(|| {
+            criterion::black_box(escape_with(LOREM_IPSUM_TEXT, |b| match b {
+                b'\t' => Some(&b"&#9;"[..]),
+                b'\n' => Some(&b"&#10;"[..]),
+                b'<' => Some(&b"&lt;"[..]),
+                b'&' => Some(&b"&amp;"[..]),
+                _ => None,
+            }));
+        })
+    });
     group.finish();
 }
 
@@ -429,6 +516,15 @@ fn unescaping(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("no_chars_to_unescape_short_to_writer", |b| {
+        let mut out = Vec::new();
+        b.iter(|| {
+            out.clear();
+            unescape_to(b"just a bit of text", &mut out).unwrap();
+            criterion::black_box(&out);
+        })
+    });
+
     group.bench_function("char_reference", |b| {
         b.iter(|| {
             let text = b"prefix &#34;some stuff&#34;,&#x22;more stuff&#x22;";
@@ -447,6 +543,16 @@ fn unescaping(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("entity_reference_to_writer", |b| {
+        let mut out = Vec::new();
+        b.iter(|| {
+            out.clear();
+            unescape_to(b"age &gt; 72 &amp;&amp; age &lt; 21", &mut out).unwrap();
+            unescape_to(b"&quot;what&apos;s that?&quot;", &mut out).unwrap();
+            criterion::black_box(&out);
+        })
+    });
+
     group.bench_function("mixed", |b| {
         let text =
 b"Lorem ipsum dolor sit amet, &amp;consectetur adipiscing elit, sed do eiusmod tempor incididunt
@@ -13,9 +13,6 @@ fuzz_target!(|data: &[u8]| {
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e))=> {
-                if e.unescaped().is_err() {
-                    break;
-                }
                 for a in e.attributes() {
                     if a.ok().map_or(false, |a| a.unescaped_value().is_err()) {
                         break;
@@ -23,8 +20,7 @@ fuzz_target!(|data: &[u8]| {
                 }
             }
             Ok(Event::Text(ref e)) | Ok(Event::Comment(ref e))
-            | Ok(Event::CData(ref e)) | Ok(Event::PI(ref e))
-            | Ok(Event::DocType(ref e)) => {
+            | Ok(Event::PI(ref e)) | Ok(Event::DocType(ref e)) => {
                 if e.unescaped().is_err() {
                     break;
                 }
@@ -34,7 +30,8 @@ fuzz_target!(|data: &[u8]| {
                 let _ = e.encoding();
                 let _ = e.standalone();
             }
-            Ok(Event::End(_)) => (),
+            Ok(Event::StartText(_)) | Ok(Event::CData(_)) | Ok(Event::End(_))
+            | Ok(Event::Attribute(_)) | Ok(Event::AttributesEnd) => (),
             Ok(Event::Eof) | Err(..) => break,
         }
         buf.clear();
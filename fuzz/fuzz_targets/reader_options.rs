@@ -0,0 +1,115 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+
+use quick_xml::events::Event;
+use quick_xml::reader::EventMask;
+use quick_xml::Reader;
+use std::borrow::Cow;
+use std::io::BufRead;
+
+/// Enables reader options by the bits of the first bytes of the input
+fn configure<R: BufRead>(reader: &mut Reader<R>, flags: u32) {
+    let flag = |i: u32| flags >> i & 1 == 1;
+    reader
+        .expand_empty_elements(flag(0))
+        .trim_text(flag(1))
+        .trim_text_end(flag(2))
+        .trim_markup_names_in_closing_tags(flag(3))
+        .check_end_names(flag(4))
+        .check_comments(flag(5))
+        .normalize_comments(flag(6))
+        .cdata_as_text(flag(7))
+        .check_cdata_end_in_text(flag(8))
+        .strip_bom(flag(9))
+        .always_emit_start_text(flag(10))
+        .preserve_raw(flag(11))
+        .validate_declaration(flag(12))
+        .strict_declaration_position(flag(13))
+        .check_attribute_values(flag(14))
+        .check_duplicate_attributes(flag(15))
+        .check_names(flag(16))
+        .require_root_element(flag(17))
+        .error_on_read_after_eof(flag(18))
+        .resync_reset_depth(flag(19))
+        .stream_attributes(flag(20));
+    if flag(21) {
+        reader.max_attributes(2).max_attribute_value_len(8);
+    }
+    if flag(22) {
+        reader.max_event_size(16);
+    }
+    if flag(23) {
+        reader.cdata_chunk_size(Some(3));
+    }
+    if flag(24) {
+        reader.void_elements([b"br".to_vec()]);
+    }
+    if flag(25) {
+        reader.skip_events(EventMask::START_TEXT | EventMask::COMMENT | EventMask::BLANK_TEXT);
+    }
+    if flag(26) {
+        reader.skip_events(EventMask::TEXT | EventMask::CDATA | EventMask::DECL | EventMask::PI);
+    }
+    if flag(27) {
+        // Returns names borrowed from the input, from a static and owned names
+        reader.map_names(|name| match name {
+            [b'a', rest @ ..] => Some(Cow::Borrowed(rest)),
+            b"b" => Some(Cow::Borrowed(b"static")),
+            [b'c', ..] => Some(Cow::Owned(name.to_ascii_uppercase())),
+            _ => None,
+        });
+    }
+    reader
+        .validate_utf8(flag(28))
+        .auto_clear_buffer(flag(29));
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 4 {
+        return;
+    }
+    let (flags, xml) = data.split_at(4);
+    let flags = u32::from_le_bytes([flags[0], flags[1], flags[2], flags[3]]);
+    // Resync after errors should not loop forever
+    let resync = flags >> 31 & 1 == 1;
+
+    // Buffered reader. The buffer is not cleared manually to check that events
+    // borrow only the new data, it is cleared only by the `auto_clear_buffer` option
+    let mut reader = Reader::from_reader(xml);
+    configure(&mut reader, flags);
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    for _ in 0..xml.len() + 16 {
+        let result = if flags >> 30 & 1 == 1 {
+            reader.read_namespaced_event(&mut buf, &mut ns_buf).map(|(_, e)| e)
+        } else {
+            reader.read_event_into(&mut buf)
+        };
+        match result {
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(_) if resync => {
+                if reader.resync().is_err() {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    // Borrowing reader
+    let mut reader = Reader::from_reader(xml);
+    configure(&mut reader, flags);
+    for _ in 0..xml.len() + 16 {
+        match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(_) if resync => {
+                if reader.resync().is_err() {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+});
@@ -0,0 +1,358 @@
+//! A pragmatic subset of [Canonical XML] for comparing documents
+//!
+//! [Canonical XML]: https://www.w3.org/TR/xml-c14n
+
+use crate::errors::{Error, Result};
+use crate::escape::unescape;
+use crate::events::{BytesStart, BytesText, Event};
+use crate::name::{Namespace, PrefixDeclaration, QName};
+use crate::reader::{Decoder, Reader};
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::io::{BufRead, Write};
+
+/// Namespace to which the `xml` prefix is bound by definition
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Reads all remaining events from the `reader` and writes their canonical
+/// form to `out`, so two documents that differ only in insignificant details
+/// produce the same output.
+///
+/// The output is always UTF-8 and follows [Canonical XML] without comments,
+/// as far as it is possible with the events provided by the [`Reader`]:
+///
+/// - the XML declaration, the DOCTYPE and comments are removed;
+/// - empty elements are written as a pair of start and end tags;
+/// - namespace declarations are written before other attributes and sorted by
+///   prefix. Declarations that do not change the binding already written by
+///   an ancestor element are removed;
+/// - other attributes are sorted by their namespace and local name and are
+///   always enclosed in double quotes;
+/// - whitespace inside tags is normalized to one space between attributes;
+/// - line endings are normalized to `\n`, and whitespace characters in
+///   attribute values are normalized to spaces;
+/// - character and entity references are replaced by the characters they
+///   refer to, and only characters listed by the specification are escaped;
+/// - CDATA sections are written as escaped text;
+/// - text outside of the root element is removed, and processing instructions
+///   outside of it are separated from the root element by `\n`.
+///
+/// Because DTDs are not processed, only the five predefined entities can be
+/// referenced, default attribute values are not added and attribute types are
+/// not taken into account when normalizing values. Text is written as
+/// returned by the reader, so configuration such as [`Reader::trim_text`]
+/// affects the output.
+///
+/// The reader should not be configured to [stream attributes], because
+/// [`Event::Attribute`] events cannot be canonicalized and are reported as
+/// [`Error::UnexpectedToken`].
+///
+/// ```
+/// use quick_xml::{canonicalize, Reader};
+///
+/// fn canonical(xml: &str) -> String {
+///     let mut reader = Reader::from_str(xml);
+///     let mut out = Vec::new();
+///     canonicalize(&mut reader, &mut out).unwrap();
+///     String::from_utf8(out).unwrap()
+/// }
+///
+/// let a = canonical("<?xml version='1.0'?><a b='1' a='&#x32;'/>");
+/// let b = canonical(r#"<a  a="2" b="1" ></a>"#);
+/// assert_eq!(a, r#"<a a="2" b="1"></a>"#);
+/// assert_eq!(a, b);
+/// ```
+///
+/// [Canonical XML]: https://www.w3.org/TR/xml-c14n
+/// [stream attributes]: Reader::stream_attributes
+pub fn canonicalize<R: BufRead, W: Write>(reader: &mut Reader<R>, out: &mut W) -> Result<()> {
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    // Namespace declarations written for each currently opened element
+    let mut scopes: Vec<Vec<(String, String)>> = Vec::new();
+    let mut after_root = false;
+
+    loop {
+        buf.clear();
+        let (_, event) = reader.read_namespaced_event(&mut buf, &mut ns_buf)?;
+        match event {
+            Event::Start(e) => {
+                write_start(reader, &e, &ns_buf, &mut scopes, out)?;
+            }
+            Event::Empty(e) => {
+                write_start(reader, &e, &ns_buf, &mut scopes, out)?;
+                write_end(reader.decoder(), e.name(), &mut scopes, out)?;
+                after_root = scopes.is_empty();
+            }
+            Event::End(e) => {
+                write_end(reader.decoder(), e.name(), &mut scopes, out)?;
+                after_root = scopes.is_empty();
+            }
+            Event::Text(e) if !scopes.is_empty() => {
+                let decoded = reader.decoder().decode(e.escaped())?;
+                let text = normalize_newlines(&decoded);
+                write_escaped(&unescape(text.as_bytes())?, false, out)?;
+            }
+            Event::CData(e) if !scopes.is_empty() => {
+                let decoded = reader.decoder().decode(&e)?;
+                write_escaped(normalize_newlines(&decoded).as_bytes(), false, out)?;
+            }
+            Event::PI(e) => {
+                if after_root {
+                    out.write_all(b"\n")?;
+                }
+                write_pi(reader.decoder(), &e, out)?;
+                if scopes.is_empty() && !after_root {
+                    out.write_all(b"\n")?;
+                }
+            }
+            Event::Attribute(_) => return Err(Error::UnexpectedToken("Attribute".to_string())),
+            Event::AttributesEnd => {
+                return Err(Error::UnexpectedToken("AttributesEnd".to_string()))
+            }
+            Event::Eof => return Ok(()),
+            // Text and CDATA outside of the root element, comments,
+            // XML declaration and DOCTYPE are not part of the canonical form
+            _ => {}
+        }
+    }
+}
+
+/// Writes a start tag with namespace declarations and attributes in
+/// the canonical order and opens a new namespace scope in `scopes`.
+fn write_start<R, W: Write>(
+    reader: &Reader<R>,
+    start: &BytesStart,
+    namespace_buffer: &[u8],
+    scopes: &mut Vec<Vec<(String, String)>>,
+    out: &mut W,
+) -> Result<()> {
+    let decoder = reader.decoder();
+    let mut declarations = Vec::new();
+    let mut attributes = Vec::new();
+    for attr in start.attributes() {
+        let attr = attr?;
+        let value = normalize_attribute(decoder, &attr.value)?;
+        if let Some(binding) = attr.key.as_namespace_binding() {
+            let prefix = match binding {
+                PrefixDeclaration::Named(prefix) => decoder.decode(prefix)?.into_owned(),
+                PrefixDeclaration::Default => String::new(),
+            };
+            // The declaration is superfluous if the nearest written binding
+            // of this prefix is the same. An absent default namespace is
+            // the same as the `xmlns=""` declaration
+            let written = scopes
+                .iter()
+                .rev()
+                .flatten()
+                .find(|(p, _)| *p == prefix)
+                .map(|(_, ns)| ns.as_str());
+            if written.unwrap_or("") != value || (written.is_none() && !prefix.is_empty()) {
+                declarations.push((prefix, value));
+            }
+        } else {
+            let (ns, local) = match attr.key.prefix() {
+                Some(prefix) if prefix.into_inner() == b"xml" => (
+                    Some(XML_NAMESPACE.to_string()),
+                    attr.key.local_name().into_inner(),
+                ),
+                _ => {
+                    let (ns, local) = reader.attribute_namespace(attr.key, namespace_buffer);
+                    let ns: Option<Namespace> = ns.try_into()?;
+                    let ns = match ns {
+                        Some(ns) => Some(normalize_attribute(decoder, ns.into_inner())?),
+                        None => None,
+                    };
+                    (ns, local.into_inner())
+                }
+            };
+            let local = decoder.decode(local)?.into_owned();
+            let name = decoder.decode(attr.key.into_inner())?.into_owned();
+            attributes.push(((ns, local), name, value));
+        }
+    }
+    declarations.sort();
+    attributes.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+    out.write_all(b"<")?;
+    out.write_all(decoder.decode(start.name().into_inner())?.as_bytes())?;
+    for (prefix, value) in declarations.iter() {
+        if prefix.is_empty() {
+            out.write_all(b" xmlns=\"")?;
+        } else {
+            out.write_all(b" xmlns:")?;
+            out.write_all(prefix.as_bytes())?;
+            out.write_all(b"=\"")?;
+        }
+        write_escaped(value.as_bytes(), true, out)?;
+        out.write_all(b"\"")?;
+    }
+    for (_, name, value) in attributes.iter() {
+        out.write_all(b" ")?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(b"=\"")?;
+        write_escaped(value.as_bytes(), true, out)?;
+        out.write_all(b"\"")?;
+    }
+    out.write_all(b">")?;
+
+    scopes.push(declarations);
+    Ok(())
+}
+
+/// Writes an end tag and closes the namespace scope opened by `write_start`
+fn write_end<W: Write>(
+    decoder: Decoder,
+    name: QName,
+    scopes: &mut Vec<Vec<(String, String)>>,
+    out: &mut W,
+) -> Result<()> {
+    scopes.pop();
+    out.write_all(b"</")?;
+    out.write_all(decoder.decode(name.into_inner())?.as_bytes())?;
+    out.write_all(b">")?;
+    Ok(())
+}
+
+/// Writes a processing instruction with exactly one space between the target
+/// and the data, omitting the space if there is no data
+fn write_pi<W: Write>(decoder: Decoder, pi: &BytesText, out: &mut W) -> Result<()> {
+    let content = normalize_newlines(&decoder.decode(pi.escaped())?).into_owned();
+    let (target, data) = match content.find(|c: char| c.is_ascii_whitespace()) {
+        Some(i) => (&content[..i], content[i..].trim_start()),
+        None => (content.as_str(), ""),
+    };
+    out.write_all(b"<?")?;
+    out.write_all(target.as_bytes())?;
+    if !data.is_empty() {
+        out.write_all(b" ")?;
+        out.write_all(data.as_bytes())?;
+    }
+    out.write_all(b"?>")?;
+    Ok(())
+}
+
+/// Decodes and normalizes an attribute value as described in the
+/// [specification], treating it as a CDATA attribute
+///
+/// [specification]: https://www.w3.org/TR/xml11/#AVNormalize
+fn normalize_attribute(decoder: Decoder, raw: &[u8]) -> Result<String> {
+    let value = normalize_newlines(&decoder.decode(raw)?).replace(['\t', '\n'], " ");
+    let value = unescape(value.as_bytes())?;
+    Ok(String::from_utf8(value.into_owned())?)
+}
+
+/// Replaces `\r\n` and single `\r` with `\n`
+fn normalize_newlines(text: &str) -> Cow<'_, str> {
+    if text.contains('\r') {
+        Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Writes text or an attribute value (if `attribute` is `true`), escaping
+/// the characters required by the canonical form
+fn write_escaped<W: Write>(text: &[u8], attribute: bool, out: &mut W) -> Result<()> {
+    let mut start = 0;
+    for (i, b) in text.iter().enumerate() {
+        let escaped: &[u8] = match b {
+            b'&' => b"&amp;",
+            b'<' => b"&lt;",
+            b'>' if !attribute => b"&gt;",
+            b'"' if attribute => b"&quot;",
+            b'\t' if attribute => b"&#x9;",
+            b'\n' if attribute => b"&#xA;",
+            b'\r' => b"&#xD;",
+            _ => continue,
+        };
+        out.write_all(&text[start..i])?;
+        out.write_all(escaped)?;
+        start = i + 1;
+    }
+    out.write_all(&text[start..])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn canonical(xml: &str) -> String {
+        let mut reader = Reader::from_str(xml);
+        let mut out = Vec::new();
+        canonicalize(&mut reader, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prolog_and_epilog() {
+        assert_eq!(
+            canonical(
+                "<?xml version='1.0'?>\n<!DOCTYPE root>\n<?pi  data ?>\n<!-- comment -->\n\
+                 <root><!-- comment --></root>\n<?pi?>\n",
+            ),
+            "<?pi data ?>\n<root></root>\n<?pi?>"
+        );
+    }
+
+    #[test]
+    fn empty_elements() {
+        assert_eq!(
+            canonical("<root><a/><b x = 'y' /></root>"),
+            r#"<root><a></a><b x="y"></b></root>"#
+        );
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            canonical("<root>a&amp;b &#60; &gt; \"'\r\n&#13;<![CDATA[<&>]]></root>"),
+            "<root>a&amp;b &lt; &gt; \"'\n&#xD;&lt;&amp;&gt;</root>"
+        );
+    }
+
+    #[test]
+    fn attribute_values() {
+        assert_eq!(
+            canonical("<root a='\"&apos;&#9;\t\r\n&#10;&lt;&gt;'/>"),
+            r#"<root a="&quot;'&#x9;  &#xA;&lt;>"></root>"#
+        );
+    }
+
+    #[test]
+    fn attributes_order() {
+        assert_eq!(
+            canonical(
+                "<root xmlns:b='urn:a' xmlns:a='urn:b' xmlns='urn:c' \
+                 a:attr='1' b:attr='2' attr='3' xml:lang='en' b:z='4'/>",
+            ),
+            r#"<root xmlns="urn:c" xmlns:a="urn:b" xmlns:b="urn:a" attr="3" xml:lang="en" b:attr="2" b:z="4" a:attr="1"></root>"#
+        );
+    }
+
+    #[test]
+    fn superfluous_namespaces() {
+        assert_eq!(
+            canonical(
+                "<root xmlns='' xmlns:a='urn:a'>\
+                   <a:child xmlns:a='urn:a' xmlns='urn:b'>\
+                     <inner xmlns='urn:b'/>\
+                     <inner xmlns=''/>\
+                   </a:child>\
+                 </root>",
+            ),
+            r#"<root xmlns:a="urn:a"><a:child xmlns="urn:b"><inner></inner><inner xmlns=""></inner></a:child></root>"#
+        );
+    }
+
+    #[test]
+    fn unknown_prefix() {
+        let mut reader = Reader::from_str("<root a:attr=''/>");
+        match canonicalize(&mut reader, &mut Vec::new()) {
+            Err(Error::UnknownPrefix(prefix)) => assert_eq!(prefix, b"a"),
+            x => panic!("Expected `Err(UnknownPrefix(a))`, but got `{:?}`", x),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Support of compressed input: gzip with the `flate` feature and automatic
+//! detection of the compression format with the `compression` feature
+
+use crate::errors::{Error, Result};
+use crate::reader::Reader;
+use flate2::bufread::MultiGzDecoder;
+#[cfg(feature = "compression")]
+use flate2::bufread::ZlibDecoder;
+#[cfg(feature = "compression")]
+use ruzstd::{FrameDecoder, StreamingDecoder};
+use std::fs::File;
+#[cfg(feature = "compression")]
+use std::io::{self, Read};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+impl<R: BufRead> Reader<BufReader<MultiGzDecoder<R>>> {
+    /// Creates an XML reader from a gzip-compressed source.
+    ///
+    /// The input is decompressed on the fly, so encoding of the document is
+    /// detected from the decompressed bytes. Inputs that consist of several
+    /// concatenated gzip members are read up to the end of the last member.
+    /// If the input is not compressed by gzip, reading of the first event
+    /// returns an [`Error::Io`].
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use flate2::{write::GzEncoder, Compression};
+    /// use quick_xml::events::{BytesStart, Event};
+    /// use quick_xml::Reader;
+    /// use std::io::Write;
+    ///
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    /// encoder.write_all(b"<tag/>").unwrap();
+    /// let compressed = encoder.finish().unwrap();
+    ///
+    /// let mut reader = Reader::from_gzip_reader(compressed.as_slice());
+    /// let mut buf = Vec::new();
+    /// assert_eq!(
+    ///     reader.read_event_into(&mut buf).unwrap(),
+    ///     Event::Empty(BytesStart::borrowed_name(b"tag"))
+    /// );
+    /// ```
+    pub fn from_gzip_reader(reader: R) -> Self {
+        Self::from_reader(BufReader::new(MultiGzDecoder::new(reader)))
+    }
+}
+
+impl Reader<BufReader<MultiGzDecoder<BufReader<File>>>> {
+    /// Creates an XML reader from a path to a gzip-compressed file.
+    ///
+    /// See [`from_gzip_reader`](Self::from_gzip_reader) for details.
+    pub fn from_gzip_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path).map_err(Error::Io)?;
+        Ok(Self::from_gzip_reader(BufReader::new(file)))
+    }
+}
+
+/// A source of XML data, that decompresses the underlying reader on the fly.
+///
+/// The compression format is detected by [`Reader::from_compressed_reader`]
+/// by the first bytes of the input:
+///
+/// |Format|Magic bytes
+/// |------|-----------
+/// |gzip  |`1F 8B`
+/// |zstd  |`28 B5 2F FD`
+/// |zlib  |a valid zlib header with the deflate method and without preset dictionary
+///
+/// If none of them matched, the input is treated as uncompressed.
+#[cfg(feature = "compression")]
+pub enum Decompressor<R: BufRead> {
+    /// Uncompressed input
+    Raw(R),
+    /// Input compressed by gzip
+    Gzip(BufReader<MultiGzDecoder<R>>),
+    /// Input compressed by zlib
+    Zlib(BufReader<ZlibDecoder<R>>),
+    /// Input compressed by zstd
+    Zstd(Box<BufReader<StreamingDecoder<R, FrameDecoder>>>),
+}
+
+#[cfg(feature = "compression")]
+impl<R: BufRead> Decompressor<R> {
+    /// Detects the compression format by peeking the first bytes of `reader`
+    /// and wraps it into the appropriate decoder.
+    ///
+    /// Only already buffered bytes are inspected, so detection can fail if
+    /// the underlying reader returns less than 4 bytes on the first read.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let start = reader.fill_buf()?;
+        Ok(if start.starts_with(b"\x1F\x8B") {
+            Self::Gzip(BufReader::new(MultiGzDecoder::new(reader)))
+        } else if start.starts_with(b"\x28\xB5\x2F\xFD") {
+            let decoder = StreamingDecoder::new(reader)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Self::Zstd(Box::new(BufReader::new(decoder)))
+        } else if is_zlib_header(start) {
+            Self::Zlib(BufReader::new(ZlibDecoder::new(reader)))
+        } else {
+            Self::Raw(reader)
+        })
+    }
+
+    /// Returns `true` if the input is decompressed, `false` if it is read as is.
+    pub fn is_compressed(&self) -> bool {
+        !matches!(self, Self::Raw(_))
+    }
+}
+
+/// Checks that `bytes` starts with a zlib header (see [RFC 1950]) with
+/// the deflate compression method and without a preset dictionary.
+///
+/// [RFC 1950]: https://www.rfc-editor.org/rfc/rfc1950#section-2.2
+#[cfg(feature = "compression")]
+fn is_zlib_header(bytes: &[u8]) -> bool {
+    match bytes {
+        [cmf, flg, ..] => {
+            // CM = 8 (deflate), CINFO <= 7 (window size up to 32K)
+            cmf & 0x0F == 8
+                && cmf >> 4 <= 7
+                // FDICT is not set
+                && flg & 0x20 == 0
+                // FCHECK
+                && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<R: BufRead> Read for Decompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Raw(r) => r.read(buf),
+            Self::Gzip(r) => r.read(buf),
+            Self::Zlib(r) => r.read(buf),
+            Self::Zstd(r) => r.read(buf),
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<R: BufRead> BufRead for Decompressor<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Raw(r) => r.fill_buf(),
+            Self::Gzip(r) => r.fill_buf(),
+            Self::Zlib(r) => r.fill_buf(),
+            Self::Zstd(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Raw(r) => r.consume(amt),
+            Self::Gzip(r) => r.consume(amt),
+            Self::Zlib(r) => r.consume(amt),
+            Self::Zstd(r) => r.consume(amt),
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<R: BufRead> Reader<Decompressor<R>> {
+    /// Creates an XML reader from a possibly compressed source.
+    ///
+    /// The compression format is detected automatically, see [`Decompressor`]
+    /// for the list of supported formats. If the input is not compressed by
+    /// any of them, it is read as is.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use flate2::{write::GzEncoder, Compression};
+    /// use quick_xml::events::{BytesStart, Event};
+    /// use quick_xml::Reader;
+    /// use std::io::Write;
+    ///
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    /// encoder.write_all(b"<tag/>").unwrap();
+    /// let compressed = encoder.finish().unwrap();
+    ///
+    /// let mut reader = Reader::from_compressed_reader(compressed.as_slice()).unwrap();
+    /// let mut buf = Vec::new();
+    /// assert_eq!(
+    ///     reader.read_event_into(&mut buf).unwrap(),
+    ///     Event::Empty(BytesStart::borrowed_name(b"tag"))
+    /// );
+    /// ```
+    pub fn from_compressed_reader(reader: R) -> Result<Self> {
+        Ok(Self::from_reader(Decompressor::new(reader)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "compression")]
+    use crate::events::BytesStart;
+    use crate::events::Event;
+    #[cfg(feature = "compression")]
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    #[cfg(feature = "compression")]
+    use flate2::Compression;
+    use pretty_assertions::assert_eq;
+    #[cfg(feature = "compression")]
+    use std::io::Write;
+
+    /// Events read from a gzipped file should be the same as from the original
+    #[test]
+    fn gzip_file() {
+        let mut plain = Reader::from_file("tests/documents/sample_1.xml").unwrap();
+        let mut gzip = Reader::from_gzip_file("tests/documents/sample_1.xml.gz").unwrap();
+
+        let mut plain_buf = Vec::new();
+        let mut gzip_buf = Vec::new();
+        loop {
+            let expected = plain.read_event_into(&mut plain_buf).unwrap();
+            assert_eq!(gzip.read_event_into(&mut gzip_buf).unwrap(), expected);
+            if expected == Event::Eof {
+                break;
+            }
+            plain_buf.clear();
+            gzip_buf.clear();
+        }
+        assert_eq!(gzip.buffer_position(), plain.buffer_position());
+    }
+
+    #[test]
+    fn gzip_not_compressed() {
+        let mut reader = Reader::from_gzip_reader(b"<root/>".as_ref());
+        let mut buf = Vec::new();
+        assert!(matches!(
+            reader.read_event_into(&mut buf),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[cfg(feature = "compression")]
+    const XML: &[u8] = b"<root><child/></root>";
+
+    #[cfg(feature = "compression")]
+    fn check(input: &[u8], compressed: bool) {
+        let mut reader = Reader::from_compressed_reader(input).unwrap();
+        assert_eq!(reader.get_ref().is_compressed(), compressed);
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            reader.read_event_into(&mut buf).unwrap(),
+            Event::Start(BytesStart::borrowed_name(b"root"))
+        );
+        assert_eq!(
+            reader.read_event_into(&mut buf).unwrap(),
+            Event::Empty(BytesStart::borrowed_name(b"child"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn raw() {
+        check(XML, false);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(XML).unwrap();
+        check(&encoder.finish().unwrap(), true);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn zlib() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(XML).unwrap();
+        check(&encoder.finish().unwrap(), true);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn zstd() {
+        // zstd frame with a single raw (uncompressed) block
+        // magic number and frame header descriptor: single segment, 1-byte content size
+        let mut compressed = b"\x28\xB5\x2F\xFD\x20".to_vec();
+        // frame content size
+        compressed.push(XML.len() as u8);
+        // block header: last block, raw block type, block size
+        let header = 1 | (XML.len() as u32) << 3;
+        compressed.extend_from_slice(&header.to_le_bytes()[..3]);
+        compressed.extend_from_slice(XML);
+        check(&compressed, true);
+    }
+
+    /// Uncompressed documents that starts with a symbol that could be a zlib
+    /// compression method should not be treated as compressed
+    #[test]
+    #[cfg(feature = "compression")]
+    fn zlib_like() {
+        assert!(!is_zlib_header(b"x?"));
+        assert!(!is_zlib_header(b"<root>"));
+        assert!(is_zlib_header(b"\x78\x9C"));
+        assert!(is_zlib_header(b"\x78\xDA"));
+    }
+}
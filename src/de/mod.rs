@@ -972,7 +972,7 @@ impl<'i, R: BufRead> XmlRead<'i> for IoReader<R> {
 
     fn read_to_end(&mut self, name: QName) -> Result<(), DeError> {
         match self.reader.read_to_end_into(name, &mut self.buf) {
-            Err(Error::UnexpectedEof(_)) => Err(DeError::UnexpectedEof),
+            Err(Error::UnexpectedEof(..)) => Err(DeError::UnexpectedEof),
             other => Ok(other?),
         }
     }
@@ -1012,7 +1012,7 @@ impl<'de> XmlRead<'de> for SliceReader<'de> {
 
     fn read_to_end(&mut self, name: QName) -> Result<(), DeError> {
         match self.reader.read_to_end(name) {
-            Err(Error::UnexpectedEof(_)) => Err(DeError::UnexpectedEof),
+            Err(Error::UnexpectedEof(..)) => Err(DeError::UnexpectedEof),
             other => Ok(other?),
         }
     }
@@ -1455,9 +1455,14 @@ mod tests {
     #[test]
     fn next_text() {
         match from_str::<String>(r#"</root>"#) {
-            Err(DeError::InvalidXml(Error::EndEventMismatch { expected, found })) => {
+            Err(DeError::InvalidXml(Error::EndEventMismatch {
+                expected,
+                found,
+                position,
+            })) => {
                 assert_eq!(expected, "");
                 assert_eq!(found, "root");
+                assert_eq!(position, 2);
             }
             x => panic!(
                 r#"Expected `Err(InvalidXml(EndEventMismatch("", "root")))`, but found {:?}"#,
@@ -1469,9 +1474,14 @@ mod tests {
         assert_eq!(s, "");
 
         match from_str::<String>(r#"<root></other>"#) {
-            Err(DeError::InvalidXml(Error::EndEventMismatch { expected, found })) => {
+            Err(DeError::InvalidXml(Error::EndEventMismatch {
+                expected,
+                found,
+                position,
+            })) => {
                 assert_eq!(expected, "root");
                 assert_eq!(found, "other");
+                assert_eq!(position, 8);
             }
             x => panic!(
                 r#"Expected `Err(InvalidXml(EndEventMismatch("root", "other")))`, but found {:?}"#,
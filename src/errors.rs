@@ -14,14 +14,17 @@ pub enum Error {
     /// Input decoding error. If `encoding` feature is disabled, contains `None`,
     /// otherwise contains the UTF-8 decoding error
     NonDecodable(Option<Utf8Error>),
-    /// Unexpected End of File
-    UnexpectedEof(String),
+    /// Unexpected End of File. Contains a description of what was expected
+    /// and a position in the input where reading of that started
+    UnexpectedEof(String, usize),
     /// End event mismatch
     EndEventMismatch {
         /// Expected end event
         expected: String,
         /// Found end event
         found: String,
+        /// Position of the mismatched end tag in the input
+        position: usize,
     },
     /// Unexpected token
     UnexpectedToken(String),
@@ -37,6 +40,101 @@ pub enum Error {
     EscapeError(EscapeError),
     /// Specified namespace prefix is unknown, cannot resolve namespace for it
     UnknownPrefix(Vec<u8>),
+    /// An element has two attributes with the same name. Returned only if
+    /// [`Reader::check_duplicate_attributes`] is enabled.
+    ///
+    /// [`Reader::check_duplicate_attributes`]: crate::Reader::check_duplicate_attributes
+    DuplicateAttribute {
+        /// Name of the duplicated attribute, as it written in the input
+        name: String,
+        /// Position of the second occurrence of the attribute in the input
+        position: usize,
+    },
+    /// An XML declaration is malformed. Returned only if
+    /// [`Reader::validate_declaration`] is enabled.
+    ///
+    /// [`Reader::validate_declaration`]: crate::Reader::validate_declaration
+    InvalidXmlDecl {
+        /// Description of the problem
+        reason: String,
+        /// Position of the declaration in the input
+        position: usize,
+    },
+    /// An element has more attributes than allowed. Returned only if
+    /// [`Reader::max_attributes`] is set.
+    ///
+    /// [`Reader::max_attributes`]: crate::Reader::max_attributes
+    TooManyAttributes {
+        /// The maximum allowed number of attributes
+        max: usize,
+        /// Position of the first attribute over the limit in the input
+        position: usize,
+    },
+    /// An attribute value is longer than allowed. Returned only if
+    /// [`Reader::max_attribute_value_len`] is set.
+    ///
+    /// [`Reader::max_attribute_value_len`]: crate::Reader::max_attribute_value_len
+    AttributeValueTooLong {
+        /// Name of the attribute, as it written in the input
+        name: String,
+        /// The maximum allowed length of a value in bytes
+        max: usize,
+        /// Position of the attribute in the input
+        position: usize,
+    },
+    /// A name of an element or an attribute does not match the [`QName`]
+    /// grammar. Returned only if [`Reader::check_names`] is enabled.
+    ///
+    /// [`QName`]: https://www.w3.org/TR/xml-names11/#NT-QName
+    /// [`Reader::check_names`]: crate::Reader::check_names
+    InvalidName {
+        /// The invalid name, as it written in the input
+        name: String,
+        /// Position of the name in the input
+        position: usize,
+    },
+    /// The document is in an encoding that was recognized by its first bytes,
+    /// but is not supported by the reader (UCS-4 or EBCDIC). Returned only if
+    /// `encoding` feature is enabled.
+    UnsupportedEncoding {
+        /// Name of the detected encoding family
+        detected: &'static str,
+    },
+    /// The input ended before any element was found. Returned only if
+    /// [`Reader::require_root_element`] is enabled.
+    ///
+    /// [`Reader::require_root_element`]: crate::Reader::require_root_element
+    NoRootElement,
+    /// An event is larger than allowed. Returned only if
+    /// [`Reader::max_event_size`] is set.
+    ///
+    /// [`Reader::max_event_size`]: crate::Reader::max_event_size
+    EventTooLarge {
+        /// The maximum allowed size of an event in bytes
+        limit: usize,
+        /// Position in the input where reading of the event content started
+        position: usize,
+    },
+    /// The DOCTYPE declaration does not contain a name of the root element,
+    /// for example, `<!DOCTYPE>`.
+    EmptyDocType {
+        /// Position of the `<` of the declaration in the input
+        position: usize,
+    },
+    /// A read was attempted after the [`Eof`] event was already returned.
+    /// Returned only if [`Reader::error_on_read_after_eof`] is enabled.
+    ///
+    /// [`Eof`]: crate::events::Event::Eof
+    /// [`Reader::error_on_read_after_eof`]: crate::Reader::error_on_read_after_eof
+    ReadAfterEof,
+    /// The XML declaration is not at the start of the document. Returned only
+    /// if [`Reader::strict_declaration_position`] is enabled.
+    ///
+    /// [`Reader::strict_declaration_position`]: crate::Reader::strict_declaration_position
+    MisplacedDeclaration {
+        /// Position of the `<` of the declaration in the input
+        position: usize,
+    },
 }
 
 impl From<::std::io::Error> for Error {
@@ -89,10 +187,10 @@ impl std::fmt::Display for Error {
             Error::Io(e) => write!(f, "I/O error: {}", e),
             Error::NonDecodable(None) => write!(f, "Malformed input, decoding impossible"),
             Error::NonDecodable(Some(e)) => write!(f, "Malformed UTF-8 input: {}", e),
-            Error::UnexpectedEof(e) => write!(f, "Unexpected EOF during reading {}", e),
-            Error::EndEventMismatch { expected, found } => {
-                write!(f, "Expecting </{}> found </{}>", expected, found)
-            }
+            Error::UnexpectedEof(e, _) => write!(f, "Unexpected EOF during reading {}", e),
+            Error::EndEventMismatch {
+                expected, found, ..
+            } => write!(f, "Expecting </{}> found </{}>", expected, found),
             Error::UnexpectedToken(e) => write!(f, "Unexpected token '{}'", e),
             Error::UnexpectedBang(b) => write!(
                 f,
@@ -112,6 +210,79 @@ impl std::fmt::Display for Error {
                 write_byte_string(f, &prefix)?;
                 f.write_str("'")
             }
+            Error::DuplicateAttribute { name, .. } => {
+                write!(f, "Duplicated attribute `{}`", name)
+            }
+            Error::InvalidXmlDecl { reason, .. } => {
+                write!(f, "Invalid XML declaration: {}", reason)
+            }
+            Error::TooManyAttributes { max, .. } => {
+                write!(f, "Element has more than {} attributes", max)
+            }
+            Error::AttributeValueTooLong { name, max, .. } => write!(
+                f,
+                "Value of attribute `{}` is longer than {} bytes",
+                name, max
+            ),
+            Error::InvalidName { name, .. } => write!(f, "Invalid name `{}`", name),
+            Error::UnsupportedEncoding { detected } => {
+                write!(f, "Unsupported encoding detected: {}", detected)
+            }
+            Error::NoRootElement => write!(f, "Document does not contain a root element"),
+            Error::EventTooLarge { limit, .. } => {
+                write!(f, "Event is larger than {} bytes", limit)
+            }
+            Error::EmptyDocType { .. } => {
+                write!(f, "DOCTYPE declaration does not contain a name")
+            }
+            Error::ReadAfterEof => write!(f, "Attempt to read after the end of input"),
+            Error::MisplacedDeclaration { .. } => {
+                write!(f, "XML declaration is not at the start of the document")
+            }
+        }
+    }
+}
+
+impl Error {
+    /// Returns a position in the input at which the error was detected, if
+    /// the error carries it.
+    ///
+    /// The position is the same as returned by [`Reader::buffer_position()`]
+    /// just after the error, but is stored in the error itself, so it remains
+    /// available when the reader is not in scope.
+    ///
+    /// [`Error::NonDecodable`] never carries a position, because it is also
+    /// returned when decoding standalone bytes which are not tied to any input.
+    /// When it is returned by the reader because of the [`validate_utf8`]
+    /// option, [`Reader::buffer_position()`] points to the first invalid byte.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<a></b>");
+    /// reader.read_event().unwrap();
+    ///
+    /// let error = reader.read_event().unwrap_err();
+    /// assert_eq!(error.position(), Some(5));
+    /// assert_eq!(error.position(), Some(reader.buffer_position()));
+    /// ```
+    ///
+    /// [`Reader::buffer_position()`]: crate::Reader::buffer_position
+    /// [`validate_utf8`]: crate::Reader::validate_utf8
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Error::UnexpectedEof(_, position) => Some(*position),
+            Error::EndEventMismatch { position, .. } => Some(*position),
+            Error::DuplicateAttribute { position, .. } => Some(*position),
+            Error::InvalidXmlDecl { position, .. } => Some(*position),
+            Error::TooManyAttributes { position, .. } => Some(*position),
+            Error::AttributeValueTooLong { position, .. } => Some(*position),
+            Error::InvalidName { position, .. } => Some(*position),
+            Error::EventTooLarge { position, .. } => Some(*position),
+            Error::EmptyDocType { position } => Some(*position),
+            Error::MisplacedDeclaration { position } => Some(*position),
+            _ => None,
         }
     }
 }
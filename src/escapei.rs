@@ -1,8 +1,10 @@
 //! Manage xml character escapes
 
+use crate::errors::Error;
 use memchr;
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Write;
 use std::ops::Range;
 
 #[cfg(test)]
@@ -29,7 +31,18 @@ pub enum EscapeError {
     /// Character is not a valid decimal value
     InvalidDecimal(char),
     /// Not a valid unicode codepoint
+    #[deprecated(note = "not returned anymore, use `InvalidCharRef` instead")]
     InvalidCodepoint(u32),
+    /// Character reference refers to a code point that is not allowed in XML
+    /// documents by the [`Char`] production: a surrogate, a control character
+    /// other than tab, line feed and carriage return, `U+FFFE`, `U+FFFF` or
+    /// a value beyond `U+10FFFF`
+    ///
+    /// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+    InvalidCharRef {
+        /// The referenced code point
+        value: u32,
+    },
 }
 
 impl std::fmt::Display for EscapeError {
@@ -56,7 +69,13 @@ impl std::fmt::Display for EscapeError {
             }
             EscapeError::TooLongDecimal => write!(f, "Cannot convert decimal to utf8"),
             EscapeError::InvalidDecimal(e) => write!(f, "'{}' is not a valid decimal character", e),
+            #[allow(deprecated)]
             EscapeError::InvalidCodepoint(n) => write!(f, "'{}' is not a valid codepoint", n),
+            EscapeError::InvalidCharRef { value } => write!(
+                f,
+                "character reference to U+{:X} is not a valid XML character",
+                value
+            ),
         }
     }
 }
@@ -66,15 +85,11 @@ impl std::error::Error for EscapeError {}
 /// Escapes a `&[u8]` and replaces all xml special characters (<, >, &, ', ") with their
 /// corresponding xml escaped value.
 pub fn escape(raw: &[u8]) -> Cow<[u8]> {
-    #[inline]
-    fn to_escape(b: u8) -> bool {
-        match b {
-            b'<' | b'>' | b'\'' | b'&' | b'"' => true,
-            _ => false,
-        }
-    }
-
-    _escape(raw, to_escape)
+    escape_with(raw, |b| match b {
+        b'\'' => Some(&b"&apos;"[..]),
+        b'"' => Some(&b"&quot;"[..]),
+        _ => escape_markup(b),
+    })
 }
 
 /// Should only be used for escaping text content. In xml text content, it is allowed
@@ -82,48 +97,62 @@ pub fn escape(raw: &[u8]) -> Cow<[u8]> {
 /// This function escapes a `&[u8]` and replaces xml special characters (<, >, &) with
 /// their corresponding xml escaped value, but does not escape quote characters.
 pub fn partial_escape(raw: &[u8]) -> Cow<[u8]> {
-    #[inline]
-    fn to_escape(b: u8) -> bool {
-        match b {
-            b'<' | b'>' | b'&' => true,
-            _ => false,
-        }
-    }
+    escape_with(raw, escape_markup)
+}
 
-    _escape(raw, to_escape)
+/// Returns the escaped value for the characters, that are escaped by both
+/// [`escape`] and [`partial_escape`]
+#[inline]
+fn escape_markup(b: u8) -> Option<&'static [u8]> {
+    match b {
+        b'<' => Some(&b"&lt;"[..]),
+        b'>' => Some(&b"&gt;"[..]),
+        b'&' => Some(&b"&amp;"[..]),
+        _ => None,
+    }
 }
 
-/// Escapes a `&[u8]` and replaces a subset of xml special characters (<, >, &, ', ") with their
-/// corresponding xml escaped value.
-fn _escape<F: Fn(u8) -> bool>(raw: &[u8], escape_chars: F) -> Cow<[u8]> {
+/// Escapes a `&[u8]` replacing bytes for which `escape_byte` returns `Some`
+/// with the returned replacement. Other bytes are copied as is.
+///
+/// This allows to build custom escapers, for example, to escape also new
+/// lines and tabs in attribute values, so they are not normalized by readers.
+/// [`escape`] and [`partial_escape`] are implemented with this function.
+/// The input is returned without allocation if no bytes were replaced.
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::escape::escape_with;
+///
+/// let escaped = escape_with(b"a\tb\n<c>", |b| match b {
+///     b'\t' => Some(&b"&#9;"[..]),
+///     b'\n' => Some(&b"&#10;"[..]),
+///     b'<' => Some(&b"&lt;"[..]),
+///     _ => None,
+/// });
+/// assert_eq!(&*escaped, b"a&#9;b&#10;&lt;c>");
+/// ```
+pub fn escape_with<'r, F>(raw: &[u8], mut escape_byte: F) -> Cow<[u8]>
+where
+    F: FnMut(u8) -> Option<&'r [u8]>,
+{
     let mut escaped = None;
-    let mut bytes = raw.iter();
     let mut pos = 0;
-    while let Some(i) = bytes.position(|&b| escape_chars(b)) {
-        if escaped.is_none() {
-            escaped = Some(Vec::with_capacity(raw.len()));
-        }
-        let escaped = escaped.as_mut().expect("initialized");
-        let new_pos = pos + i;
-        escaped.extend_from_slice(&raw[pos..new_pos]);
-        match raw[new_pos] {
-            b'<' => escaped.extend_from_slice(b"&lt;"),
-            b'>' => escaped.extend_from_slice(b"&gt;"),
-            b'\'' => escaped.extend_from_slice(b"&apos;"),
-            b'&' => escaped.extend_from_slice(b"&amp;"),
-            b'"' => escaped.extend_from_slice(b"&quot;"),
-            _ => unreachable!("Only '<', '>','\', '&' and '\"' are escaped"),
+    for (i, &b) in raw.iter().enumerate() {
+        if let Some(replacement) = escape_byte(b) {
+            let escaped = escaped.get_or_insert_with(|| Vec::with_capacity(raw.len()));
+            escaped.extend_from_slice(&raw[pos..i]);
+            escaped.extend_from_slice(replacement);
+            pos = i + 1;
         }
-        pos = new_pos + 1;
     }
 
-    if let Some(mut escaped) = escaped {
-        if let Some(raw) = raw.get(pos..) {
-            escaped.extend_from_slice(raw);
+    match escaped {
+        Some(mut escaped) => {
+            escaped.extend_from_slice(&raw[pos..]);
+            Cow::Owned(escaped)
         }
-        Cow::Owned(escaped)
-    } else {
-        Cow::Borrowed(raw)
+        None => Cow::Borrowed(raw),
     }
 }
 
@@ -156,50 +185,217 @@ pub fn do_unescape<'a>(
     raw: &'a [u8],
     custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
 ) -> Result<Cow<'a, [u8]>, EscapeError> {
-    let mut unescaped = None;
+    if memchr::memchr(b'&', raw).is_none() {
+        return Ok(Cow::Borrowed(raw));
+    }
+    let mut unescaped = Vec::with_capacity(raw.len());
+    unescape_parts(raw, custom_entities, |part| {
+        unescaped.extend_from_slice(part.bytes(raw));
+        Ok::<_, EscapeError>(())
+    })?;
+    Ok(Cow::Owned(unescaped))
+}
+
+/// Unescapes a `&[u8]` and writes the result to `out` piece by piece, without
+/// building the whole unescaped value in memory.
+///
+/// Parts of the input without escape sequences are written as is, so this
+/// function is useful for unescaping large texts directly to a file or socket.
+/// If an error is returned, the part of the input before the invalid escape
+/// sequence is already written to `out`.
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::escape::unescape_to;
+///
+/// let mut out = Vec::new();
+/// unescape_to(b"1 &lt; 2 &amp;&amp; 3 &#x3E; 2", &mut out).unwrap();
+/// assert_eq!(out, b"1 < 2 && 3 > 2");
+/// ```
+pub fn unescape_to<W: Write>(raw: &[u8], out: &mut W) -> crate::Result<()> {
+    unescape_parts(raw, None, |part| {
+        out.write_all(part.bytes(raw)).map_err(Error::Io)
+    })
+}
+
+/// Unescapes a `&str` and appends the result to `out`
+pub(crate) fn unescape_str_to(raw: &str, out: &mut String) -> Result<(), EscapeError> {
+    unescape_parts(raw.as_bytes(), None, |part| {
+        match part {
+            Part::Raw(range) => out.push_str(&raw[range]),
+            Part::Char(c) => out.push_str(c),
+            // Not used without custom entities
+            Part::Custom(value) => out.push_str(&String::from_utf8_lossy(value)),
+        }
+        Ok(())
+    })
+}
+
+/// A part of the unescaped content
+enum Part<'a> {
+    /// A part of the escaped content without escape sequences
+    Raw(Range<usize>),
+    /// A character, referenced by a predefined entity or a character reference
+    Char(&'a str),
+    /// The value of a custom entity
+    Custom(&'a [u8]),
+}
+
+impl<'a> Part<'a> {
+    /// Returns the bytes of this part of the unescaped `raw` content
+    #[inline]
+    fn bytes(self, raw: &'a [u8]) -> &'a [u8] {
+        match self {
+            Part::Raw(range) => &raw[range],
+            Part::Char(c) => c.as_bytes(),
+            Part::Custom(value) => value,
+        }
+    }
+}
+
+/// Unescapes `raw` and calls `write` for each unescaped part of it
+#[inline]
+fn unescape_parts<E, F>(
+    raw: &[u8],
+    custom_entities: Option<&HashMap<Vec<u8>, Vec<u8>>>,
+    mut write: F,
+) -> Result<(), E>
+where
+    E: From<EscapeError>,
+    F: FnMut(Part) -> Result<(), E>,
+{
     let mut last_end = 0;
     let mut iter = memchr::memchr2_iter(b'&', b';', raw);
     while let Some(start) = iter.by_ref().find(|p| raw[*p] == b'&') {
         match iter.next() {
             Some(end) if raw[end] == b';' => {
                 // append valid data
-                if unescaped.is_none() {
-                    unescaped = Some(Vec::with_capacity(raw.len()));
-                }
-                let unescaped = unescaped.as_mut().expect("initialized");
-                unescaped.extend_from_slice(&raw[last_end..start]);
+                write(Part::Raw(last_end..start))?;
 
                 // search for character correctness
                 let pat = &raw[start + 1..end];
                 if let Some(s) = named_entity(pat) {
-                    unescaped.extend_from_slice(s.as_bytes());
+                    write(Part::Char(s))?;
                 } else if pat.starts_with(b"#") {
-                    push_utf8(unescaped, parse_number(&pat[1..], start..end)?);
+                    let mut buf = [0u8; 4];
+                    let c = parse_number(&pat[1..], start..end)?;
+                    write(Part::Char(c.encode_utf8(&mut buf)))?;
                 } else if let Some(value) = custom_entities.and_then(|hm| hm.get(pat)) {
-                    unescaped.extend_from_slice(&value);
+                    write(Part::Custom(value))?;
                 } else {
                     return Err(EscapeError::UnrecognizedSymbol(
                         start + 1..end,
                         String::from_utf8(pat.to_vec()),
-                    ));
+                    )
+                    .into());
                 }
 
                 last_end = end + 1;
             }
-            _ => return Err(EscapeError::UnterminatedEntity(start..raw.len())),
+            _ => return Err(EscapeError::UnterminatedEntity(start..raw.len()).into()),
         }
     }
+    write(Part::Raw(last_end..raw.len()))
+}
+
+/// The way how an entity reference is spelled in the escaped content
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityKind {
+    /// Named entity reference, like `&amp;`
+    Named,
+    /// Decimal character reference, like `&#38;`
+    Decimal,
+    /// Hexadecimal character reference, like `&#x26;`
+    Hexadecimal,
+}
+
+/// An entity or character reference, found in the escaped content, together
+/// with its original spelling.
+///
+/// Unescaping loses the information how the character was represented in the
+/// source document: `&amp;`, `&#38;` and `&#x26;` all produce the same `&`.
+/// This struct preserves that information, which is required for faithful
+/// round-tripping of a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityRef<'a> {
+    /// Position of the reference in the escaped content, including the leading
+    /// `&` and the trailing `;`
+    pub range: Range<usize>,
+    /// The reference exactly as written in the source, without the leading `&`
+    /// and the trailing `;`, for example, `amp`, `#38` or `#x26`
+    pub spelling: &'a [u8],
+    /// The kind of the reference
+    pub kind: EntityKind,
+}
 
-    if let Some(mut unescaped) = unescaped {
-        if let Some(raw) = raw.get(last_end..) {
-            unescaped.extend_from_slice(raw);
+/// An iterator over entity and character references in the escaped content.
+///
+/// Created by the [`entities`] function.
+#[derive(Clone, Debug)]
+pub struct Entities<'a> {
+    raw: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Iterator for Entities<'a> {
+    type Item = Result<EntityRef<'a>, EscapeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.position + memchr::memchr(b'&', &self.raw[self.position..])?;
+        match memchr::memchr(b';', &self.raw[start..]) {
+            Some(i) => {
+                let end = start + i;
+                self.position = end + 1;
+
+                let spelling = &self.raw[start + 1..end];
+                let kind = if spelling.starts_with(b"#x") {
+                    EntityKind::Hexadecimal
+                } else if spelling.starts_with(b"#") {
+                    EntityKind::Decimal
+                } else {
+                    EntityKind::Named
+                };
+                Some(Ok(EntityRef {
+                    range: start..end + 1,
+                    spelling,
+                    kind,
+                }))
+            }
+            None => {
+                self.position = self.raw.len();
+                Some(Err(EscapeError::UnterminatedEntity(start..self.raw.len())))
+            }
         }
-        Ok(Cow::Owned(unescaped))
-    } else {
-        Ok(Cow::Borrowed(raw))
     }
 }
 
+impl<'a> std::iter::FusedIterator for Entities<'a> {}
+
+/// Returns an iterator over all entity and character references in the escaped
+/// `raw` content, preserving their original spelling.
+///
+/// References are not resolved, so this function does not fail on unknown
+/// entities. The only error that can be returned is [`EscapeError::UnterminatedEntity`],
+/// after which the iteration ends.
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::escape::{entities, EntityKind};
+///
+/// let spellings: Vec<_> = entities(b"&amp; &#38; &#x26;")
+///     .map(|e| e.unwrap())
+///     .map(|e| (e.spelling, e.kind))
+///     .collect();
+/// assert_eq!(spellings, vec![
+///     (b"amp".as_ref(), EntityKind::Named),
+///     (b"#38".as_ref(), EntityKind::Decimal),
+///     (b"#x26".as_ref(), EntityKind::Hexadecimal),
+/// ]);
+/// ```
+pub fn entities(raw: &[u8]) -> Entities<'_> {
+    Entities { raw, position: 0 }
+}
+
 #[cfg(not(feature = "escape-html"))]
 const fn named_entity(name: &[u8]) -> Option<&str> {
     let s = match name {
@@ -1675,26 +1871,41 @@ const fn named_entity(name: &[u8]) -> Option<&str> {
     Some(s)
 }
 
-fn push_utf8(out: &mut Vec<u8>, code: char) {
-    let mut buf = [0u8; 4];
-    out.extend_from_slice(code.encode_utf8(&mut buf).as_bytes());
-}
-
 fn parse_number(bytes: &[u8], range: Range<usize>) -> Result<char, EscapeError> {
+    let digits = bytes.strip_prefix(b"x").unwrap_or(bytes);
+    if digits.is_empty() {
+        return Err(EscapeError::UnrecognizedSymbol(
+            range.start + 1..range.end,
+            String::from_utf8([b"#", bytes].concat()),
+        ));
+    }
     let code = if bytes.starts_with(b"x") {
-        parse_hexadecimal(&bytes[1..])
+        parse_hexadecimal(digits)
     } else {
-        parse_decimal(&bytes)
+        parse_decimal(digits)
     }?;
     if code == 0 {
         return Err(EscapeError::EntityWithNull(range));
     }
+    if !is_xml_char(code) {
+        return Err(EscapeError::InvalidCharRef { value: code });
+    }
     match std::char::from_u32(code) {
         Some(c) => Ok(c),
-        None => Err(EscapeError::InvalidCodepoint(code)),
+        None => Err(EscapeError::InvalidCharRef { value: code }),
     }
 }
 
+/// Checks that the code point matches the [`Char`] production of XML 1.0
+///
+/// [`Char`]: https://www.w3.org/TR/xml/#NT-Char
+fn is_xml_char(code: u32) -> bool {
+    matches!(
+        code,
+        0x9 | 0xA | 0xD | 0x20..=0xD7FF | 0xE000..=0xFFFD | 0x10000..=0x10FFFF
+    )
+}
+
 fn parse_hexadecimal(bytes: &[u8]) -> Result<u32, EscapeError> {
     // maximum code is 0x10FFFF => 6 characters
     if bytes.len() > 6 {
@@ -1738,6 +1949,77 @@ fn test_unescape() {
     assert!(unescape(b"&foo;").is_err());
 }
 
+#[test]
+fn test_unescape_invalid_char_ref() {
+    // surrogate
+    match unescape(b"&#xD800;") {
+        Err(EscapeError::InvalidCharRef { value: 0xD800 }) => {}
+        x => panic!("expected `InvalidCharRef(0xD800)`, got {:?}", x),
+    }
+    // beyond the Unicode range
+    match unescape(b"&#x110000;") {
+        Err(EscapeError::InvalidCharRef { value: 0x110000 }) => {}
+        x => panic!("expected `InvalidCharRef(0x110000)`, got {:?}", x),
+    }
+    // control characters
+    match unescape(b"&#1;") {
+        Err(EscapeError::InvalidCharRef { value: 1 }) => {}
+        x => panic!("expected `InvalidCharRef(1)`, got {:?}", x),
+    }
+    match unescape(b"&#xFFFE;") {
+        Err(EscapeError::InvalidCharRef { value: 0xFFFE }) => {}
+        x => panic!("expected `InvalidCharRef(0xFFFE)`, got {:?}", x),
+    }
+    match unescape(b"a&#0;") {
+        Err(EscapeError::EntityWithNull(range)) => assert_eq!(range, 1..4),
+        x => panic!("expected `EntityWithNull`, got {:?}", x),
+    }
+    // no digits
+    match unescape(b"&#x;") {
+        Err(EscapeError::UnrecognizedSymbol(range, Ok(symbol))) => {
+            assert_eq!(range, 1..3);
+            assert_eq!(symbol, "#x");
+        }
+        x => panic!("expected `UnrecognizedSymbol`, got {:?}", x),
+    }
+    // too long references are rejected without parsing them
+    match unescape(b"&#00000000000000000000000000065;") {
+        Err(EscapeError::TooLongDecimal) => {}
+        x => panic!("expected `TooLongDecimal`, got {:?}", x),
+    }
+    match unescape(b"&#x0000000041;") {
+        Err(EscapeError::TooLongHexadecimal) => {}
+        x => panic!("expected `TooLongHexadecimal`, got {:?}", x),
+    }
+
+    // allowed control characters and boundaries of ranges
+    assert_eq!(&*unescape(b"&#9;&#xA;&#13;").unwrap(), b"\t\n\r");
+    assert_eq!(&*unescape(b"&#xD7FF;").unwrap(), "\u{D7FF}".as_bytes());
+    assert_eq!(&*unescape(b"&#xE000;").unwrap(), "\u{E000}".as_bytes());
+    assert_eq!(&*unescape(b"&#x10FFFF;").unwrap(), "\u{10FFFF}".as_bytes());
+}
+
+#[test]
+fn test_unescape_to() {
+    let mut out = Vec::new();
+    unescape_to(b"test", &mut out).unwrap();
+    assert_eq!(out, b"test");
+
+    out.clear();
+    unescape_to(b"&lt;test&gt; &#x30;&#48;", &mut out).unwrap();
+    assert_eq!(out, b"<test> 00");
+
+    // Part before the invalid reference is already written
+    out.clear();
+    match unescape_to(b"a &lt; b &foo; c", &mut out) {
+        Err(crate::Error::EscapeError(EscapeError::UnrecognizedSymbol(range, _))) => {
+            assert_eq!(range, 10..13)
+        }
+        x => panic!("expected `UnrecognizedSymbol`, got {:?}", x),
+    }
+    assert_eq!(out, b"a < b ");
+}
+
 #[test]
 fn test_unescape_with() {
     let custom_entities = vec![(b"foo".to_vec(), b"BAR".to_vec())]
@@ -1766,6 +2048,19 @@ fn test_escape() {
     );
 }
 
+/// Input without characters to escape should be returned as is, without allocation
+#[test]
+fn test_escape_borrowed() {
+    let long = "no chars to escape ".repeat(100);
+    for input in [&b""[..], b"test", long.as_bytes()] {
+        assert!(matches!(escape(input), Cow::Borrowed(b) if b == input));
+        assert!(matches!(partial_escape(input), Cow::Borrowed(b) if b == input));
+    }
+    assert!(matches!(partial_escape(b"'a' \"b\""), Cow::Borrowed(_)));
+    assert!(matches!(escape(b"a<b"), Cow::Owned(_)));
+    assert!(matches!(partial_escape(b"a<b"), Cow::Owned(_)));
+}
+
 #[test]
 fn test_partial_escape() {
     assert_eq!(&*partial_escape(b"test"), b"test");
@@ -1777,3 +2072,70 @@ fn test_partial_escape() {
         "prefix_\"a\"b&amp;&lt;&gt;c".as_bytes()
     );
 }
+
+#[test]
+fn test_escape_with() {
+    let escape_ws = |b| match b {
+        b'\t' => Some(&b"&#9;"[..]),
+        b'\n' => Some(&b"&#10;"[..]),
+        _ => None,
+    };
+    assert!(matches!(
+        escape_with(b"a<b>", escape_ws),
+        Cow::Borrowed(b"a<b>")
+    ));
+    assert_eq!(&*escape_with(b"\ta\nb\n", escape_ws), b"&#9;a&#10;b&#10;");
+
+    // Replacements are not limited by static strings
+    let replacement = String::from("[x]");
+    assert_eq!(
+        &*escape_with(b"xyx", |b| (b == b'x').then(|| replacement.as_bytes())),
+        b"[x]y[x]"
+    );
+}
+
+#[test]
+fn test_entities() {
+    let raw = b"a &lt;b&#60; &#x3C;&unknown;";
+    let found: Vec<_> = entities(raw).map(|e| e.unwrap()).collect();
+    assert_eq!(
+        found,
+        vec![
+            EntityRef {
+                range: 2..6,
+                spelling: b"lt",
+                kind: EntityKind::Named,
+            },
+            EntityRef {
+                range: 7..12,
+                spelling: b"#60",
+                kind: EntityKind::Decimal,
+            },
+            EntityRef {
+                range: 13..19,
+                spelling: b"#x3C",
+                kind: EntityKind::Hexadecimal,
+            },
+            EntityRef {
+                range: 19..28,
+                spelling: b"unknown",
+                kind: EntityKind::Named,
+            },
+        ]
+    );
+    for e in found {
+        assert_eq!(raw[e.range.start], b'&');
+        assert_eq!(raw[e.range.end - 1], b';');
+    }
+
+    let mut iter = entities(b"&amp; &lt");
+    assert!(iter.next().unwrap().is_ok());
+    match iter.next() {
+        Some(Err(EscapeError::UnterminatedEntity(range))) => assert_eq!(range, 6..9),
+        x => panic!(
+            "Expected `Some(Err(UnterminatedEntity(_)))`, but got `{:?}`",
+            x
+        ),
+    }
+    assert!(iter.next().is_none());
+}
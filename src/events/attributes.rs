@@ -3,9 +3,9 @@
 //! Provides an iterator over attributes key/value pairs
 
 use crate::errors::{Error, Result as XmlResult};
-use crate::escape::{do_unescape, escape};
+use crate::escape::{do_unescape, escape, unescape_str_to};
 use crate::name::QName;
-use crate::reader::{is_whitespace, Reader};
+use crate::reader::{is_whitespace, Decoder, Reader};
 use crate::utils::{write_byte_string, write_cow_string, Bytes};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::iter::FusedIterator;
@@ -32,6 +32,13 @@ pub struct Attribute<'a> {
 }
 
 impl<'a> Attribute<'a> {
+    /// Returns the length of the raw value in bytes, that is, before
+    /// unescaping.
+    #[inline]
+    pub fn value_len(&self) -> usize {
+        self.value.len()
+    }
+
     /// Returns the unescaped value.
     ///
     /// This is normally the value you are interested in. Escape sequences such as `&gt;` are
@@ -40,6 +47,17 @@ impl<'a> Attribute<'a> {
     /// This will allocate if the value contains any escape sequences.
     ///
     /// See also [`unescaped_value_with_custom_entities()`](#method.unescaped_value_with_custom_entities)
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesStart;
+    ///
+    /// let start = BytesStart::borrowed(br#"tag attr="&lt;x&gt;""#, 3);
+    /// let attr = start.attributes().next().unwrap().unwrap();
+    ///
+    /// assert_eq!(attr.value_len(), 9);
+    /// assert_eq!(&*attr.unescaped_value().unwrap(), b"<x>");
+    /// ```
     pub fn unescaped_value(&self) -> XmlResult<Cow<[u8]>> {
         self.make_unescaped_value(None)
     }
@@ -71,7 +89,7 @@ impl<'a> Attribute<'a> {
         do_unescape(&*self.value, custom_entities).map_err(Error::EscapeError)
     }
 
-    /// Decode then unescapes the value
+    /// Decode then unescapes the value, using [entities] of the reader
     ///
     /// This allocates a `String` in all cases. For performance reasons it might be a better idea to
     /// instead use one of:
@@ -79,10 +97,11 @@ impl<'a> Attribute<'a> {
     /// * [`Reader::decode()`], as it only allocates when the decoding can't be performed otherwise.
     /// * [`unescaped_value()`], as it doesn't allocate when no escape sequences are used.
     ///
+    /// [entities]: Reader::entities
     /// [`unescaped_value()`]: #method.unescaped_value
     /// [`Reader::decode()`]: ../../reader/struct.Reader.html#method.decode
     pub fn unescape_and_decode_value<B>(&self, reader: &Reader<B>) -> XmlResult<String> {
-        self.do_unescape_and_decode_value(reader, None)
+        self.do_unescape_and_decode_value(reader, reader.custom_entities())
     }
 
     /// Decode then unescapes the value with custom entities
@@ -107,6 +126,63 @@ impl<'a> Attribute<'a> {
         self.do_unescape_and_decode_value(reader, Some(custom_entities))
     }
 
+    /// Decodes and unescapes the value using [`Reader::decode_and_unescape`].
+    ///
+    /// Unlike [`unescape_and_decode_value()`] this method does not allocate if
+    /// the value does not require transcoding to UTF-8 and does not contain
+    /// escape sequences.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesStart;
+    /// use quick_xml::Reader;
+    /// use std::borrow::Cow;
+    ///
+    /// let reader = Reader::from_str("");
+    /// let tag = BytesStart::borrowed(br#"tag a="1 &lt; 2" b='x'"#, 3);
+    /// let mut attributes = tag.attributes().map(|a| a.unwrap());
+    ///
+    /// let a = attributes.next().unwrap();
+    /// assert_eq!(a.decode_and_unescape_value(&reader).unwrap(), "1 < 2");
+    /// let b = attributes.next().unwrap();
+    /// assert_eq!(b.decode_and_unescape_value(&reader).unwrap(), Cow::Borrowed("x"));
+    /// ```
+    ///
+    /// [`unescape_and_decode_value()`]: Self::unescape_and_decode_value
+    pub fn decode_and_unescape_value<B>(&self, reader: &Reader<B>) -> XmlResult<Cow<'_, str>> {
+        reader.decode_and_unescape(&self.value)
+    }
+
+    /// Decodes then unescapes the value and appends it to the `out` buffer.
+    ///
+    /// Unlike [`unescape_and_decode_value()`] this method does not allocate a new
+    /// `String` for each value, so the same buffer can be reused for many attributes:
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesStart;
+    /// use quick_xml::Reader;
+    ///
+    /// let reader = Reader::from_str("");
+    /// let tag = BytesStart::borrowed(br#"tag a="1 &lt; 2" b='x'"#, 3);
+    ///
+    /// let mut value = String::new();
+    /// for attr in tag.attributes() {
+    ///     value.clear();
+    ///     attr.unwrap().unescape_value_into(&mut value, reader.decoder()).unwrap();
+    ///     // ... use `value`
+    /// }
+    /// assert_eq!(value, "x");
+    /// ```
+    ///
+    /// If an error is returned, `out` may contain a part of the decoded value.
+    ///
+    /// [`unescape_and_decode_value()`]: Self::unescape_and_decode_value
+    pub fn unescape_value_into(&self, out: &mut String, decoder: Decoder) -> XmlResult<()> {
+        let decoded = decoder.decode(&self.value)?;
+        Ok(unescape_str_to(&decoded, out)?)
+    }
+
     /// The keys and values of `custom_entities`, if any, must be valid UTF-8.
     fn do_unescape_and_decode_value<B>(
         &self,
@@ -200,6 +276,33 @@ pub struct Attributes<'a> {
 
 impl<'a> Attributes<'a> {
     /// Creates a new attribute iterator from a buffer.
+    ///
+    /// Attributes are parsed by the same rules as attributes of elements read
+    /// by the [`Reader`], starting at `pos` in the `buf`. Usually `buf` is
+    /// the content of a tag without `<` and `>` and `pos` is the end of the
+    /// element name, but a standalone attribute string can be parsed as well,
+    /// with `pos` equal to zero. Positions in errors are offsets in `buf`.
+    ///
+    /// Duplicated attributes are reported as [`AttrError::Duplicated`]
+    /// errors, use [`with_checks(false)`] to disable this check. Malformed
+    /// attributes are reported regardless of this setting.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::attributes::{AttrError, Attribute, Attributes};
+    ///
+    /// let mut iter = Attributes::new(br#"a="1" b='2' a="3""#, 0);
+    /// assert_eq!(iter.next(), Some(Ok(Attribute::from(("a", "1")))));
+    /// assert_eq!(iter.next(), Some(Ok(Attribute::from(("b", "2")))));
+    /// assert_eq!(iter.next(), Some(Err(AttrError::Duplicated(12, 0))));
+    ///
+    /// let mut iter = Attributes::new(br#"a="1" b='2' a="3""#, 0);
+    /// iter.with_checks(false);
+    /// assert_eq!(iter.nth(2), Some(Ok(Attribute::from(("a", "3")))));
+    /// ```
+    ///
+    /// [`Reader`]: crate::Reader
+    /// [`with_checks(false)`]: Self::with_checks
     pub fn new(buf: &'a [u8], pos: usize) -> Self {
         Self {
             bytes: buf,
@@ -225,6 +328,30 @@ impl<'a> Attributes<'a> {
         self.state.check_duplicates = val;
         self
     }
+
+    /// Converts this iterator into an iterator that also returns the exact
+    /// markup of each attribute, including whitespaces around the key and
+    /// the `=` sign and the quotes around the value.
+    ///
+    /// This allows to distinguish documents that differs only in formatting
+    /// of the attributes from documents with different attribute values.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::attributes::Attributes;
+    ///
+    /// let mut iter = Attributes::new(b"tag  key = 'value'", 3).spanned();
+    /// let attr = iter.next().unwrap().unwrap();
+    ///
+    /// assert_eq!(attr.raw, b"  key = 'value'");
+    /// assert_eq!(attr.leading, b"  ");
+    /// assert_eq!(attr.separator, b" = ");
+    /// assert_eq!(attr.quote, Some(b'\''));
+    /// assert_eq!(&*attr.attribute.value, b"value");
+    /// ```
+    pub fn spanned(self) -> SpannedAttributes<'a> {
+        SpannedAttributes { inner: self }
+    }
 }
 
 impl<'a> Iterator for Attributes<'a> {
@@ -242,6 +369,71 @@ impl<'a> Iterator for Attributes<'a> {
 
 impl<'a> FusedIterator for Attributes<'a> {}
 
+/// An attribute together with the markup from which it was parsed.
+///
+/// Returned by the [`SpannedAttributes`] iterator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpannedAttribute<'a> {
+    /// The parsed attribute
+    pub attribute: Attribute<'a>,
+    /// The whole markup of the attribute, starting right after the previous
+    /// attribute (or element name) and ending after the closing quote
+    pub raw: &'a [u8],
+    /// Position of [`raw`](Self::raw) in the buffer of the element
+    pub span: Range<usize>,
+    /// Whitespaces before the attribute key
+    pub leading: &'a [u8],
+    /// Bytes between the key and the opening quote of the value, that is
+    /// the `=` sign with whitespaces around it. Empty for HTML attributes
+    /// without value
+    pub separator: &'a [u8],
+    /// Quote character (`"` or `'`), or `None` for HTML attributes with
+    /// unquoted value or without value
+    pub quote: Option<u8>,
+}
+
+/// Iterator over XML attributes, that also returns their formatting.
+///
+/// Created by [`Attributes::spanned`].
+#[derive(Clone, Debug)]
+pub struct SpannedAttributes<'a> {
+    inner: Attributes<'a>,
+}
+
+impl<'a> Iterator for SpannedAttributes<'a> {
+    type Item = Result<SpannedAttribute<'a>, AttrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.inner.bytes;
+        let start = self.inner.state.recover(bytes)?;
+        let attr = match self.inner.state.next(bytes)? {
+            Ok(attr) => attr,
+            Err(e) => return Some(Err(e)),
+        };
+        let (key, value, quote) = match attr.clone() {
+            Attr::DoubleQ(key, value) => (key, Some(value), Some(b'"')),
+            Attr::SingleQ(key, value) => (key, Some(value), Some(b'\'')),
+            Attr::Unquoted(key, value) => (key, Some(value), None),
+            Attr::Empty(key) => (key, None, None),
+        };
+        let quote_len = quote.map_or(0, |_| 1);
+        let (separator, end) = match value {
+            Some(value) => (key.end..value.start - quote_len, value.end + quote_len),
+            None => (key.end..key.end, key.end),
+        };
+        Some(Ok(SpannedAttribute {
+            attribute: attr.map(|range| &bytes[range]).into(),
+            raw: &bytes[start..end],
+            span: start..end,
+            leading: &bytes[start..key.start],
+            separator: &bytes[separator],
+            quote,
+        }))
+    }
+}
+
+impl<'a> FusedIterator for SpannedAttributes<'a> {}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Errors that can be raised during parsing attributes.
@@ -530,6 +722,12 @@ impl IterState {
         }
     }
 
+    /// Changes whether attributes should be checked for uniqueness
+    pub fn with_checks(mut self, val: bool) -> Self {
+        self.check_duplicates = val;
+        self
+    }
+
     /// Recover from an error that could have been made on a previous step.
     /// Returns an offset from which parsing should continue.
     /// If there no input left, returns `None`.
@@ -2247,3 +2445,146 @@ mod html {
         assert_eq!(iter.next(), None);
     }
 }
+
+/// Checks that formatting of attributes is reported correctly
+#[cfg(test)]
+mod spanned {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn xml() {
+        let mut iter = Attributes::new(b"tag a='1'  b = \"2\"\tc=''", 3).spanned();
+
+        assert_eq!(
+            iter.next(),
+            Some(Ok(SpannedAttribute {
+                attribute: Attribute::from(("a", "1")),
+                raw: b" a='1'",
+                span: 3..9,
+                leading: b" ",
+                separator: b"=",
+                quote: Some(b'\''),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok(SpannedAttribute {
+                attribute: Attribute::from(("b", "2")),
+                raw: b"  b = \"2\"",
+                span: 9..18,
+                leading: b"  ",
+                separator: b" = ",
+                quote: Some(b'"'),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok(SpannedAttribute {
+                attribute: Attribute::from(("c", "")),
+                raw: b"\tc=''",
+                span: 18..23,
+                leading: b"\t",
+                separator: b"=",
+                quote: Some(b'\''),
+            }))
+        );
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn html() {
+        let mut iter = Attributes::html(b"tag a = 1 b", 3).spanned();
+
+        assert_eq!(
+            iter.next(),
+            Some(Ok(SpannedAttribute {
+                attribute: Attribute::from(("a", "1")),
+                raw: b" a = 1",
+                span: 3..9,
+                leading: b" ",
+                separator: b" = ",
+                quote: None,
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok(SpannedAttribute {
+                attribute: Attribute::from(("b", "")),
+                raw: b" b",
+                span: 9..11,
+                leading: b" ",
+                separator: b"",
+                quote: None,
+            }))
+        );
+        assert_eq!(iter.next(), None);
+    }
+
+    /// Errors are reported as in the ordinary iterator, and the markup of
+    /// the erroneous attribute is not included into the next one
+    #[test]
+    fn after_error() {
+        let mut iter = Attributes::new(b"tag a=1 b='2'", 3).spanned();
+        //                               0     ^ = 6
+
+        assert_eq!(iter.next(), Some(Err(AttrError::UnquotedValue(6))));
+        assert_eq!(
+            iter.next(),
+            Some(Ok(SpannedAttribute {
+                attribute: Attribute::from(("b", "2")),
+                raw: b" b='2'",
+                span: 7..13,
+                leading: b" ",
+                separator: b"=",
+                quote: Some(b'\''),
+            }))
+        );
+        assert_eq!(iter.next(), None);
+    }
+}
+
+#[cfg(test)]
+mod value {
+    use super::*;
+    use crate::escape::EscapeError;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unescaped() {
+        let mut iter = Attributes::new(br#"tag a="&lt;x&gt;" b='x'"#, 3);
+
+        let attr = iter.next().unwrap().unwrap();
+        assert_eq!(attr.value_len(), 9);
+        assert_eq!(
+            attr.unescaped_value().unwrap(),
+            Cow::<[u8]>::Owned(b"<x>".to_vec())
+        );
+
+        // Value without escape sequences is not copied
+        let attr = iter.next().unwrap().unwrap();
+        assert_eq!(attr.value_len(), 1);
+        assert_eq!(attr.unescaped_value().unwrap(), Cow::Borrowed(b"x"));
+    }
+
+    #[test]
+    fn unescaped_with() {
+        let attr = Attribute::from((b"a".as_ref(), b"&lt;&custom;&gt;".as_ref()));
+        let mut entities = HashMap::new();
+        entities.insert(b"custom".to_vec(), b"value".to_vec());
+
+        assert_eq!(
+            &*attr
+                .unescaped_value_with_custom_entities(&entities)
+                .unwrap(),
+            b"<value>"
+        );
+        match attr.unescaped_value() {
+            Err(Error::EscapeError(EscapeError::UnrecognizedSymbol(range, _))) => {
+                assert_eq!(range, 5..11)
+            }
+            x => panic!("Expected `UnrecognizedSymbol`, got {:?}", x),
+        }
+    }
+}
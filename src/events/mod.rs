@@ -38,16 +38,18 @@ pub mod attributes;
 use encoding_rs::Encoding;
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryInto;
 use std::fmt::{self, Debug, Formatter};
+use std::io;
 use std::ops::Deref;
 use std::str::from_utf8;
 
 use crate::errors::{Error, Result};
-use crate::escape::{do_unescape, escape, partial_escape};
-use crate::name::{LocalName, QName};
-use crate::reader::{Decoder, Reader};
+use crate::escape::{do_unescape, entities, escape, partial_escape, Entities};
+use crate::name::{LocalName, Namespace, QName};
+use crate::reader::{is_whitespace, trim_whitespace, Decoder, Reader};
 use crate::utils::write_cow_string;
-use attributes::{Attribute, Attributes};
+use attributes::{Attr, Attribute, Attributes};
 
 #[cfg(feature = "serialize")]
 use crate::escape::EscapeError;
@@ -104,6 +106,10 @@ impl<'a> From<BytesText<'a>> for BytesStartText<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// An attribute with its name split into an optional prefix and a local name,
+/// followed by its raw value. Returned by [`BytesStart::attributes_split`].
+pub type SplitAttribute<'a> = (Option<&'a [u8]>, LocalName<'a>, Cow<'a, [u8]>);
+
 /// Opening tag data (`Event::Start`), with optional attributes.
 ///
 /// `<name attr="value">`.
@@ -283,6 +289,35 @@ impl<'a> BytesStart<'a> {
         bytes.push(b'"');
     }
 
+    /// Adds an attribute to this element, escaping its value.
+    ///
+    /// Unlike [`push_attribute`], which writes the value as is, this method
+    /// replaces all XML special characters (`<`, `>`, `&`, `'` and `"`) in
+    /// the `value` with their escaped form, so any value can be written safely.
+    /// The value is always enclosed in double quotes. The `name` is not escaped.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesStart;
+    ///
+    /// let mut start = BytesStart::borrowed_name(b"tag");
+    /// start.push_attribute_escaped((b"attr", br#"<"a" & 'b'>"#));
+    /// assert_eq!(
+    ///     &*start,
+    ///     br#"tag attr="&lt;&quot;a&quot; &amp; &apos;b&apos;&gt;""#.as_ref()
+    /// );
+    /// ```
+    ///
+    /// [`push_attribute`]: Self::push_attribute
+    pub fn push_attribute_escaped(&mut self, (name, value): (&[u8], &[u8])) {
+        let bytes = self.buf.to_mut();
+        bytes.push(b' ');
+        bytes.extend_from_slice(name);
+        bytes.extend_from_slice(b"=\"");
+        bytes.extend_from_slice(&escape(value));
+        bytes.push(b'"');
+    }
+
     /// Remove all attributes from the ByteStart
     pub fn clear_attributes(&mut self) -> &mut BytesStart<'a> {
         self.buf.to_mut().truncate(self.name_len);
@@ -294,6 +329,163 @@ impl<'a> BytesStart<'a> {
         Attributes::new(&self.buf, self.name_len)
     }
 
+    /// Returns an iterator over the attributes of this tag with values decoded
+    /// using the decoder of the `reader`, and unescaped if `unescape` is `true`.
+    /// Entities from the [`Reader::entities`] table are unescaped too.
+    ///
+    /// This is a shortcut for the common case when you need only string values
+    /// of attributes. Each item is an error if the attribute is malformed or
+    /// if its value cannot be decoded (when the `encoding` feature is enabled)
+    /// or unescaped. Iteration can be continued after a decoding error.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::name::QName;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<tag a='1 &lt; 2' b='text'/>");
+    /// match reader.read_event().unwrap() {
+    ///     Event::Empty(e) => {
+    ///         let attributes = e
+    ///             .attributes_decoded(&reader, true)
+    ///             .collect::<Result<Vec<_>, _>>()
+    ///             .unwrap();
+    ///         assert_eq!(
+    ///             attributes,
+    ///             vec![(QName(b"a"), "1 < 2".into()), (QName(b"b"), "text".into())]
+    ///         );
+    ///     }
+    ///     e => panic!("Expected `Empty` event, got {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// [`Reader::entities`]: crate::Reader::entities
+    pub fn attributes_decoded<'r, B>(
+        &'r self,
+        reader: &'r Reader<B>,
+        unescape: bool,
+    ) -> impl Iterator<Item = Result<(QName<'r>, Cow<'r, str>)>> + 'r {
+        let decoder = reader.decoder();
+        self.attributes().map(move |attr| {
+            let attr = attr?;
+            let value = match attr.value {
+                Cow::Borrowed(value) if unescape => reader.decode_and_unescape(value)?,
+                Cow::Borrowed(value) => decoder.decode(value)?,
+                Cow::Owned(value) if unescape => {
+                    Cow::Owned(reader.decode_and_unescape(&value)?.into_owned())
+                }
+                Cow::Owned(value) => Cow::Owned(decoder.decode(&value)?.into_owned()),
+            };
+            Ok((attr.key, value))
+        })
+    }
+
+    /// Returns an iterator over the attributes of this tag with their names
+    /// split into a prefix and a local name at the first `:`.
+    ///
+    /// Names are not resolved to namespaces, so this method does not need
+    /// a namespace resolver and is useful for tools that work with prefixes
+    /// directly. Namespace declarations are split as any other names:
+    /// `xmlns` has no prefix and the local name `xmlns`, and `xmlns:foo` has
+    /// the prefix `xmlns` and the local name `foo`.
+    ///
+    /// Values are returned as is, without unescaping.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesStart;
+    ///
+    /// let start = BytesStart::borrowed(br#"tag xmlns:p="urn:p" p:a="1" b="2""#, 3);
+    /// let mut attributes = start.attributes_split().map(Result::unwrap);
+    ///
+    /// let (prefix, local, value) = attributes.next().unwrap();
+    /// assert_eq!((prefix, local.as_ref(), &*value), (Some(&b"xmlns"[..]), &b"p"[..], &b"urn:p"[..]));
+    ///
+    /// let (prefix, local, value) = attributes.next().unwrap();
+    /// assert_eq!((prefix, local.as_ref(), &*value), (Some(&b"p"[..]), &b"a"[..], &b"1"[..]));
+    ///
+    /// let (prefix, local, value) = attributes.next().unwrap();
+    /// assert_eq!((prefix, local.as_ref(), &*value), (None, &b"b"[..], &b"2"[..]));
+    /// assert_eq!(attributes.next(), None);
+    /// ```
+    pub fn attributes_split(&self) -> impl Iterator<Item = Result<SplitAttribute<'_>>> {
+        self.attributes().map(|attr| {
+            let attr = attr?;
+            let (local, prefix) = attr.key.decompose();
+            Ok((prefix.map(|p| p.into_inner()), local, attr.value))
+        })
+    }
+
+    /// Returns all attributes of this tag sorted by their names.
+    ///
+    /// This is useful for comparing elements or for canonicalization, when
+    /// the order of attributes should not matter. Unlike [`attributes()`],
+    /// this method allocates, so prefer the iterator when the order is not
+    /// important.
+    ///
+    /// Returns an error if any attribute is malformed or duplicated.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::attributes::Attribute;
+    /// use quick_xml::events::BytesStart;
+    ///
+    /// let start = BytesStart::borrowed(br#"tag b="2" c="3" a="1""#, 3);
+    /// assert_eq!(
+    ///     start.attributes_sorted().unwrap(),
+    ///     vec![
+    ///         Attribute::from(("a", "1")),
+    ///         Attribute::from(("b", "2")),
+    ///         Attribute::from(("c", "3")),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`attributes()`]: Self::attributes
+    pub fn attributes_sorted(&self) -> Result<Vec<Attribute<'_>>> {
+        let mut attributes = self
+            .attributes()
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        attributes.sort_by(|a, b| a.key.as_ref().cmp(b.key.as_ref()));
+        Ok(attributes)
+    }
+
+    /// Returns all attributes of this tag sorted by their namespace and local
+    /// name, resolved in the current scope of the `reader`.
+    ///
+    /// Namespace declarations (`xmlns` and `xmlns:*` attributes) are placed
+    /// first and sorted by their names. Other attributes without namespace
+    /// are placed before attributes with namespace. Attributes from the same
+    /// namespace are sorted by local names.
+    ///
+    /// This method is intended to be used for the `Start` and `Empty` events,
+    /// returned by the [`Reader::read_namespaced_event`], with the same
+    /// `namespace_buffer`.
+    ///
+    /// Returns an error if any attribute is malformed or duplicated, or if
+    /// an attribute prefix is not bound to a namespace.
+    pub fn attributes_sorted_ns<R>(
+        &self,
+        reader: &Reader<R>,
+        namespace_buffer: &[u8],
+    ) -> Result<Vec<Attribute<'_>>> {
+        let mut attributes = Vec::new();
+        for attr in self.attributes() {
+            let attr = attr?;
+            let key = if attr.key.as_namespace_binding().is_some() {
+                (false, None, attr.key.into_inner())
+            } else {
+                let (ns, local) = reader.attribute_namespace(attr.key, namespace_buffer);
+                let ns: Option<Namespace> = ns.try_into()?;
+                (true, ns.map(|ns| ns.into_inner()), local.into_inner())
+            };
+            attributes.push((key, attr));
+        }
+        attributes.sort_by_key(|(a, _)| *a);
+        Ok(attributes.into_iter().map(|(_, attr)| attr).collect())
+    }
+
     /// Returns an iterator over the HTML-like attributes of this tag (no mandatory quotes or `=`).
     pub fn html_attributes(&self) -> Attributes {
         Attributes::html(self, self.name_len)
@@ -306,6 +498,26 @@ impl<'a> BytesStart<'a> {
         &self.buf[self.name_len..]
     }
 
+    /// Returns `true` if this tag does not have any attributes, i.e. there is
+    /// nothing except whitespaces after the tag name.
+    ///
+    /// This check is cheaper than `self.attributes().next().is_none()`, because
+    /// attributes are not parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quick_xml::events::BytesStart;
+    ///
+    /// assert!(BytesStart::borrowed(b"tag", 3).is_empty_of_attributes());
+    /// assert!(BytesStart::borrowed(b"tag  ", 3).is_empty_of_attributes());
+    /// assert!(!BytesStart::borrowed(b"tag attr='value'", 3).is_empty_of_attributes());
+    /// ```
+    #[inline]
+    pub fn is_empty_of_attributes(&self) -> bool {
+        self.attributes_raw().iter().all(|&b| is_whitespace(b))
+    }
+
     /// Try to get an attribute
     pub fn try_get_attribute<N: AsRef<[u8]> + Sized>(
         &'a self,
@@ -347,6 +559,17 @@ pub struct BytesDecl<'a> {
     element: BytesStart<'a>,
 }
 
+/// Parsed content of an XML declaration, returned by [`BytesDecl::parsed()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XmlDecl {
+    /// XML version, usually `1.0` or `1.1`
+    pub version: String,
+    /// Declared encoding of the document, if specified
+    pub encoding: Option<String>,
+    /// Standalone flag: `true` for `yes`, `false` for `no`, `None` if not specified
+    pub standalone: Option<bool>,
+}
+
 impl<'a> BytesDecl<'a> {
     /// Creates a `BytesDecl` from a `BytesStart`
     pub fn from_start(start: BytesStart<'a>) -> BytesDecl<'a> {
@@ -506,6 +729,55 @@ impl<'a> BytesDecl<'a> {
             .transpose()
     }
 
+    /// Parses all fields of the declaration at once into an owned [`XmlDecl`].
+    ///
+    /// This is a convenient alternative to calling [`version()`], [`encoding()`]
+    /// and [`standalone()`] separately and handling their results. The raw
+    /// declaration remains available through this `BytesDecl`.
+    ///
+    /// In addition to errors, returned by the methods above, this method returns
+    /// [`Error::UnexpectedToken`] if the standalone flag is neither `yes` nor `no`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesDecl, BytesStart, XmlDecl};
+    ///
+    /// // <?xml version='1.0' encoding='utf-8' standalone='yes'?>
+    /// let decl = BytesDecl::from_start(BytesStart::borrowed(
+    ///     b" version='1.0' encoding='utf-8' standalone='yes'",
+    ///     0,
+    /// ));
+    /// assert_eq!(
+    ///     decl.parsed().unwrap(),
+    ///     XmlDecl {
+    ///         version: "1.0".to_string(),
+    ///         encoding: Some("utf-8".to_string()),
+    ///         standalone: Some(true),
+    ///     }
+    /// );
+    /// ```
+    ///
+    /// [`version()`]: Self::version
+    /// [`encoding()`]: Self::encoding
+    /// [`standalone()`]: Self::standalone
+    pub fn parsed(&self) -> Result<XmlDecl> {
+        let to_string = |v: Cow<[u8]>| -> Result<String> { Ok(from_utf8(&v)?.to_string()) };
+
+        let standalone = match self.standalone().transpose()? {
+            None => None,
+            Some(v) if v.as_ref() == b"yes" => Some(true),
+            Some(v) if v.as_ref() == b"no" => Some(false),
+            Some(v) => return Err(Error::UnexpectedToken(to_string(v)?)),
+        };
+        Ok(XmlDecl {
+            version: to_string(self.version()?)?,
+            encoding: self.encoding().transpose()?.map(to_string).transpose()?,
+            standalone,
+        })
+    }
+
     /// Constructs a new `XmlDecl` from the (mandatory) _version_ (should be `1.0` or `1.1`),
     /// the optional _encoding_ (e.g., `UTF-8`) and the optional _standalone_ (`yes` or `no`)
     /// attribute.
@@ -582,7 +854,10 @@ impl<'a> Deref for BytesDecl<'a> {
 /// A struct to manage `Event::End` events
 #[derive(Clone, Eq, PartialEq)]
 pub struct BytesEnd<'a> {
+    /// Name of the tag, possibly followed by whitespaces
     name: Cow<'a, [u8]>,
+    /// Length of the name in `name`
+    name_len: usize,
 }
 
 impl<'a> BytesEnd<'a> {
@@ -591,6 +866,19 @@ impl<'a> BytesEnd<'a> {
     pub fn borrowed(name: &'a [u8]) -> BytesEnd<'a> {
         BytesEnd {
             name: Cow::Borrowed(name),
+            name_len: name.len(),
+        }
+    }
+
+    /// Creates a new `BytesEnd` borrowing a slice, which contains the name
+    /// followed by whitespaces, as read with [`Reader::preserve_raw`].
+    ///
+    /// [`Reader::preserve_raw`]: crate::Reader::preserve_raw
+    #[inline]
+    pub(crate) fn borrowed_raw(content: &'a [u8], name_len: usize) -> BytesEnd<'a> {
+        BytesEnd {
+            name: Cow::Borrowed(content),
+            name_len,
         }
     }
 
@@ -598,6 +886,7 @@ impl<'a> BytesEnd<'a> {
     #[inline]
     pub fn owned(name: Vec<u8>) -> BytesEnd<'static> {
         BytesEnd {
+            name_len: name.len(),
             name: Cow::Owned(name),
         }
     }
@@ -606,13 +895,25 @@ impl<'a> BytesEnd<'a> {
     pub fn into_owned(self) -> BytesEnd<'static> {
         BytesEnd {
             name: Cow::Owned(self.name.into_owned()),
+            name_len: self.name_len,
         }
     }
 
+    /// Extracts the inner `Cow` from the `BytesEnd` event container.
+    #[inline]
+    pub fn into_inner(self) -> Cow<'a, [u8]> {
+        self.name
+    }
+
     /// Gets the undecoded raw tag name, as present in the input stream.
+    ///
+    /// Whitespaces after the name, which are kept in the event when reading
+    /// with [`Reader::preserve_raw`], are not included.
+    ///
+    /// [`Reader::preserve_raw`]: crate::Reader::preserve_raw
     #[inline]
     pub fn name(&self) -> QName {
-        QName(&*self.name)
+        QName(&self.name[..self.name_len])
     }
 
     /// Gets the undecoded raw local tag name (excluding namespace) as present
@@ -700,6 +1001,54 @@ impl<'a> BytesText<'a> {
         self.content
     }
 
+    /// Returns the content without leading and trailing whitespaces (spaces,
+    /// tabs, carriage returns and new lines).
+    ///
+    /// This is useful for [`Comment`] events, which content usually is
+    /// surrounded by spaces. The content is not unescaped.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesText;
+    ///
+    /// let comment = BytesText::from_escaped_str(" @directive key=value\n");
+    /// assert_eq!(comment.trimmed(), b"@directive key=value");
+    /// ```
+    ///
+    /// [`Comment`]: Event::Comment
+    #[inline]
+    pub fn trimmed(&self) -> &[u8] {
+        trim_whitespace(&self.content)
+    }
+
+    /// Converts this text to a CDATA section with the same content.
+    ///
+    /// The text is unescaped and the result is wrapped into [`BytesCData`].
+    /// Because the CDATA section cannot contain the `]]>` sequence, an
+    /// [`Error::UnexpectedToken`] is returned if the unescaped text contains it.
+    /// This is the inverse of [`BytesCData::escape`].
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesCData, BytesText};
+    ///
+    /// let text = BytesText::from_escaped_str("1 &lt; 2");
+    /// assert_eq!(text.into_cdata().unwrap(), BytesCData::from_str("1 < 2"));
+    ///
+    /// let text = BytesText::from_escaped_str("]]&gt;");
+    /// assert!(text.into_cdata().is_err());
+    /// ```
+    pub fn into_cdata(self) -> Result<BytesCData<'a>> {
+        let content = match do_unescape(&self.content, None)? {
+            Cow::Borrowed(_) => self.content,
+            Cow::Owned(unescaped) => Cow::Owned(unescaped),
+        };
+        if content.windows(3).any(|w| w == b"]]>") {
+            return Err(Error::UnexpectedToken("]]>".to_string()));
+        }
+        Ok(BytesCData::new(content))
+    }
+
     /// Returns unescaped version of the text content, that can be written
     /// as CDATA in XML
     #[cfg(feature = "serialize")]
@@ -749,13 +1098,16 @@ impl<'a> BytesText<'a> {
     }
 
     /// helper method to unescape then decode self using the reader encoding
+    /// and [entities] of the reader
     ///
     /// for performance reasons (could avoid allocating a `String`),
     /// it might be wiser to manually use
     /// 1. BytesText::unescaped()
     /// 2. Reader::decode(...)
+    ///
+    /// [entities]: Reader::entities
     pub fn unescape_and_decode<B>(&self, reader: &Reader<B>) -> Result<String> {
-        self.do_unescape_and_decode_with_custom_entities(reader, None)
+        self.do_unescape_and_decode_with_custom_entities(reader, reader.custom_entities())
     }
 
     /// helper method to unescape then decode self using the reader encoding with custom entities
@@ -791,6 +1143,89 @@ impl<'a> BytesText<'a> {
     pub fn escaped(&self) -> &[u8] {
         self.content.as_ref()
     }
+
+    /// Returns an iterator over entity and character references in the content
+    /// with their original spelling.
+    ///
+    /// Because the content is stored in escaped form, writing this event back
+    /// always reproduces references as they were in the source. This method
+    /// allows to inspect them, for example, to find out whether `&` was written
+    /// as `&amp;` or as `&#38;`.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::escape::EntityKind;
+    /// use quick_xml::events::BytesText;
+    ///
+    /// let text = BytesText::from_escaped_str("&#38; &amp;");
+    /// let kinds: Vec<_> = text.entities().map(|e| e.unwrap().kind).collect();
+    /// assert_eq!(kinds, vec![EntityKind::Decimal, EntityKind::Named]);
+    /// ```
+    pub fn entities(&self) -> Entities<'_> {
+        entities(&self.content)
+    }
+
+    /// Returns an iterator over whitespace-separated tokens of the content.
+    ///
+    /// This is useful for formats, that pack lists of values into a text node,
+    /// such as `list` types of XML Schema. Runs of XML whitespace characters
+    /// (space, tab, `\r` and `\n`) separate tokens, empty tokens are skipped.
+    ///
+    /// The content is split in the *escaped* form, so character references to
+    /// whitespace characters (such as `&#32;`) do not separate tokens. Use
+    /// [`tokens_unescaped`] to get unescaped and decoded tokens.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::BytesText;
+    ///
+    /// let text = BytesText::from_escaped_str(" 1,2\t3,4\n\n5,6 ");
+    /// let tokens: Vec<_> = text.tokens().collect();
+    /// assert_eq!(tokens, vec![&b"1,2"[..], b"3,4", b"5,6"]);
+    /// ```
+    ///
+    /// [`tokens_unescaped`]: Self::tokens_unescaped
+    pub fn tokens(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.content
+            .split(|&b| is_whitespace(b))
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Returns an iterator over whitespace-separated tokens of the content,
+    /// each of them decoded using `decoder` and then unescaped.
+    ///
+    /// Tokens are split as in [`tokens`], so a character reference to
+    /// a whitespace character becomes a part of the unescaped token. Tokens,
+    /// that do not contain references and do not need decoding, are borrowed
+    /// from the content.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::{BytesText, Event};
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<list>a&amp;b c</list>");
+    /// reader.read_event().unwrap();
+    /// match reader.read_event().unwrap() {
+    ///     Event::Text(text) => {
+    ///         let tokens = text
+    ///             .tokens_unescaped(reader.decoder())
+    ///             .collect::<Result<Vec<_>, _>>()
+    ///             .unwrap();
+    ///         assert_eq!(tokens, vec!["a&b", "c"]);
+    ///     }
+    ///     e => panic!("Expected `Text` event, got {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// [`tokens`]: Self::tokens
+    pub fn tokens_unescaped(
+        &self,
+        decoder: Decoder,
+    ) -> impl Iterator<Item = Result<Cow<'_, str>>> + '_ {
+        self.tokens()
+            .map(move |token| decoder.decode_and_unescape(token))
+    }
 }
 
 impl<'a> Debug for BytesText<'a> {
@@ -866,6 +1301,9 @@ impl<'a> BytesCData<'a> {
     /// | `&`       | `&amp;`
     /// | `'`       | `&apos;`
     /// | `"`       | `&quot;`
+    ///
+    /// Because `>` is escaped, the `]]>` sequence never appears in the result,
+    /// so the conversion is safe and can be reversed with [`BytesText::into_cdata`].
     pub fn escape(self) -> BytesText<'a> {
         BytesText::from_escaped(match escape(&self.content) {
             Cow::Borrowed(_) => self.content,
@@ -951,6 +1389,8 @@ pub enum Event<'a> {
     /// // XML in UTF-8 with BOM
     /// let xml = b"\xEF\xBB\xBF<?xml version='1.0'?>";
     /// let mut reader = Reader::from_bytes(xml);
+    /// // Do not skip the BOM, which is done by default
+    /// reader.strip_bom(false);
     /// let mut events_processed = 0;
     /// loop {
     ///     match reader.read_event() {
@@ -994,6 +1434,13 @@ pub enum Event<'a> {
     PI(BytesText<'a>),
     /// Doctype `<!DOCTYPE ...>`.
     DocType(BytesText<'a>),
+    /// An attribute of the last `Start` or `Empty` element. Generated only
+    /// if [`Reader::stream_attributes`] is enabled.
+    ///
+    /// [`Reader::stream_attributes`]: crate::Reader::stream_attributes
+    Attribute(Attr<Cow<'a, [u8]>>),
+    /// Generated after the last [`Event::Attribute`] of an element.
+    AttributesEnd,
     /// End of XML document.
     Eof,
 }
@@ -1013,9 +1460,107 @@ impl<'a> Event<'a> {
             Event::Decl(e) => Event::Decl(e.into_owned()),
             Event::PI(e) => Event::PI(e.into_owned()),
             Event::DocType(e) => Event::DocType(e.into_owned()),
+            Event::Attribute(a) => Event::Attribute(a.map(|c| Cow::Owned(c.into_owned()))),
+            Event::AttributesEnd => Event::AttributesEnd,
             Event::Eof => Event::Eof,
         }
     }
+
+    /// Returns the name of the element for the [`Start`], [`Empty`] and [`End`]
+    /// events and `None` for all other events.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::name::QName;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str("<root><child/>text</root>");
+    /// let mut events = Vec::new();
+    /// loop {
+    ///     match reader.read_event().unwrap() {
+    ///         Event::Eof => break,
+    ///         e => events.push(e),
+    ///     }
+    /// }
+    ///
+    /// let names: Vec<_> = events.iter().filter_map(|e| e.name()).collect();
+    /// assert_eq!(names, vec![QName(b"root"), QName(b"child"), QName(b"root")]);
+    /// ```
+    ///
+    /// [`Start`]: Self::Start
+    /// [`Empty`]: Self::Empty
+    /// [`End`]: Self::End
+    pub fn name(&self) -> Option<QName<'_>> {
+        match self {
+            Event::Start(e) | Event::Empty(e) => Some(e.name()),
+            Event::End(e) => Some(e.name()),
+            _ => None,
+        }
+    }
+
+    /// Writes this event to `out` as XML markup.
+    ///
+    /// The content of events is written as is, so the output reproduces the
+    /// input of the [`Reader`] for events that were not changed (except for
+    /// the parts of markup that the reader normalizes, for example, spaces
+    /// before `>` in end tags). Text is already escaped in events and is not
+    /// escaped again, attributes keep their original bytes. [`Eof`] writes
+    /// nothing.
+    ///
+    /// Unlike [`Writer`], this method does not keep any state, so it cannot
+    /// write [`Attribute`] and [`AttributesEnd`] events, which are the parts of
+    /// the preceding start tag. An error with the [`InvalidInput`] kind is
+    /// returned for them.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::events::Event;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str(r#"<a x="1"><!--c--><b/>text &amp; more</a>"#);
+    /// let mut out = Vec::new();
+    /// loop {
+    ///     match reader.read_event().unwrap() {
+    ///         Event::Eof => break,
+    ///         // Drop comments
+    ///         Event::Comment(_) => {}
+    ///         e => e.write_to(&mut out).unwrap(),
+    ///     }
+    /// }
+    /// assert_eq!(out, br#"<a x="1"><b/>text &amp; more</a>"#);
+    /// ```
+    ///
+    /// [`Reader`]: crate::reader::Reader
+    /// [`Eof`]: Self::Eof
+    /// [`Writer`]: crate::writer::Writer
+    /// [`Attribute`]: Self::Attribute
+    /// [`AttributesEnd`]: Self::AttributesEnd
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
+    pub fn write_to<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        let (start, content, end): (&[u8], &[u8], &[u8]) = match self {
+            Event::StartText(e) => (b"", e, b""),
+            Event::Start(e) => (b"<", e, b">"),
+            Event::End(e) => (b"</", e, b">"),
+            Event::Empty(e) => (b"<", e, b"/>"),
+            Event::Text(e) => (b"", e.escaped(), b""),
+            Event::Comment(e) => (b"<!--", e, b"-->"),
+            Event::CData(e) => (b"<![CDATA[", e, b"]]>"),
+            Event::Decl(e) => (b"<?", e, b"?>"),
+            Event::PI(e) => (b"<?", e, b"?>"),
+            Event::DocType(e) => (b"<!DOCTYPE ", e, b">"),
+            Event::Attribute(_) | Event::AttributesEnd => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "attributes cannot be written after the start tag",
+                ))
+            }
+            Event::Eof => return Ok(()),
+        };
+        out.write_all(start)?;
+        out.write_all(content)?;
+        out.write_all(end)
+    }
 }
 
 impl<'a> Deref for Event<'a> {
@@ -1032,7 +1577,11 @@ impl<'a> Deref for Event<'a> {
             Event::CData(ref e) => &*e,
             Event::Comment(ref e) => &*e,
             Event::DocType(ref e) => &*e,
-            Event::Eof => &[],
+            Event::Attribute(ref a) => match a {
+                Attr::DoubleQ(_, v) | Attr::SingleQ(_, v) | Attr::Unquoted(_, v) => v,
+                Attr::Empty(_) => &[],
+            },
+            Event::AttributesEnd | Event::Eof => &[],
         }
     }
 }
@@ -1112,6 +1661,24 @@ mod test {
         assert_eq!(b.name(), QName(b"g"));
     }
 
+    #[test]
+    fn bytestart_push_attribute_escaped() {
+        let value = br#"1 < 2 & "a" > 'b'"#;
+        let mut start = BytesStart::borrowed_name(b"tag");
+        start.push_attribute_escaped((b"attr", value));
+
+        let xml = format!("<{}/>", from_utf8(&start).unwrap());
+        let mut reader = Reader::from_str(&xml);
+        match reader.read_event().unwrap() {
+            Event::Empty(e) => {
+                let attr = e.attributes().next().unwrap().unwrap();
+                assert_eq!(attr.key, QName(b"attr"));
+                assert_eq!(&*attr.unescaped_value().unwrap(), value.as_ref());
+            }
+            e => panic!("Expected `Empty` event, got {:?}", e),
+        }
+    }
+
     #[test]
     fn bytestart_clear_attributes() {
         let mut b = BytesStart::owned_name("test");
@@ -1122,4 +1689,139 @@ mod test {
         assert_eq!(b.len(), 4);
         assert_eq!(b.name(), QName(b"test"));
     }
+
+    #[test]
+    fn bytestart_is_empty_of_attributes() {
+        let mut b = BytesStart::owned_name("test");
+        assert!(b.is_empty_of_attributes());
+        b.push_attribute(("x", "y"));
+        assert!(!b.is_empty_of_attributes());
+        b.clear_attributes();
+        assert!(b.is_empty_of_attributes());
+    }
+
+    #[test]
+    fn bytestart_attributes_split() {
+        let b = BytesStart::borrowed(
+            br#"test xmlns="urn:d" xmlns:foo="urn:foo" foo:a="1" b='&lt;' c:d:e="2""#,
+            4,
+        );
+        let attributes: Vec<_> = b
+            .attributes_split()
+            .map(|a| {
+                let (prefix, local, value) = a.unwrap();
+                (prefix, local.into_inner(), value)
+            })
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                (None, &b"xmlns"[..], Cow::Borrowed(&b"urn:d"[..])),
+                (Some(&b"xmlns"[..]), b"foo", Cow::Borrowed(b"urn:foo")),
+                (Some(b"foo"), b"a", Cow::Borrowed(b"1")),
+                (None, b"b", Cow::Borrowed(b"&lt;")),
+                // Split happens at the first `:`
+                (Some(b"c"), b"d:e", Cow::Borrowed(b"2")),
+            ]
+        );
+
+        let b = BytesStart::borrowed(b"test a='1' a", 4);
+        let mut attributes = b.attributes_split();
+        assert!(attributes.next().unwrap().is_ok());
+        assert!(attributes.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn cdata_text_roundtrip() {
+        let cdata = BytesCData::from_str("<a> & ]]> \"'");
+        let text = cdata.clone().escape();
+        assert_eq!(
+            text.escaped(),
+            b"&lt;a&gt; &amp; ]]&gt; &quot;&apos;".as_ref()
+        );
+        match text.into_cdata() {
+            Err(Error::UnexpectedToken(token)) => assert_eq!(token, "]]>"),
+            x => panic!("Expected `Err(UnexpectedToken(_))`, but got `{:?}`", x),
+        }
+
+        let cdata = BytesCData::from_str("<a> & \"'");
+        assert_eq!(cdata.clone().escape().into_cdata().unwrap(), cdata);
+
+        let text = BytesText::from_escaped_str("no escapes");
+        match text.into_cdata().unwrap().into_inner() {
+            Cow::Borrowed(b) => assert_eq!(b, b"no escapes"),
+            Cow::Owned(_) => panic!("content should be borrowed"),
+        }
+    }
+
+    #[test]
+    fn bytesdecl_parsed() {
+        let decl = BytesDecl::new(b"1.1", None, Some(b"no"));
+        assert_eq!(
+            decl.parsed().unwrap(),
+            XmlDecl {
+                version: "1.1".to_string(),
+                encoding: None,
+                standalone: Some(false),
+            }
+        );
+
+        let decl = BytesDecl::new(b"1.0", None, Some(b"maybe"));
+        match decl.parsed() {
+            Err(Error::UnexpectedToken(token)) => assert_eq!(token, "maybe"),
+            x => panic!("Expected `Err(UnexpectedToken(_))`, but got `{:?}`", x),
+        }
+
+        let decl = BytesDecl::from_start(BytesStart::borrowed(b" encoding='utf-8'", 0));
+        match decl.parsed() {
+            Err(Error::XmlDeclWithoutVersion(Some(found))) => assert_eq!(found, "encoding"),
+            x => panic!(
+                "Expected `Err(XmlDeclWithoutVersion(_))`, but got `{:?}`",
+                x
+            ),
+        }
+    }
+
+    #[test]
+    fn event_write_to() {
+        let xml = "\n<?xml version='1.0'?><!DOCTYPE root [<!ENTITY e 'x'>]>\
+                   <root a='1' b=\"&lt;\">text &amp;<!--c--><![CDATA[<x>]]><?pi x?><e/></root>";
+        let mut reader = Reader::from_str(xml);
+        let mut out = Vec::new();
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Eof => break,
+                e => e.write_to(&mut out).unwrap(),
+            }
+        }
+        assert_eq!(from_utf8(&out).unwrap(), xml);
+
+        let mut reader = Reader::from_str("<a x='1'/>");
+        reader.stream_attributes(true);
+        reader.read_event().unwrap();
+        let attr = reader.read_event().unwrap();
+        assert!(matches!(attr, Event::Attribute(_)));
+        match attr.write_to(&mut out) {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidInput),
+            x => panic!("Expected `InvalidInput` error, got {:?}", x),
+        }
+        assert!(Event::Eof.write_to(&mut Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn event_name() {
+        let start = Event::Start(BytesStart::borrowed(b"tag attr='1'", 3));
+        assert_eq!(start.name(), Some(QName(b"tag")));
+        let empty = Event::Empty(BytesStart::borrowed_name(b"empty"));
+        assert_eq!(empty.name(), Some(QName(b"empty")));
+        let end = Event::End(BytesEnd::borrowed(b"ns:tag"));
+        assert_eq!(end.name(), Some(QName(b"ns:tag")));
+
+        assert_eq!(Event::Text(BytesText::from_escaped_str("tag")).name(), None);
+        assert_eq!(
+            Event::Comment(BytesText::from_escaped_str("tag")).name(),
+            None
+        );
+        assert_eq!(Event::Eof.name(), None);
+    }
 }
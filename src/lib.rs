@@ -133,28 +133,45 @@
 #![deny(missing_docs)]
 #![recursion_limit = "1024"]
 
+mod canonical;
+#[cfg(feature = "flate")]
+mod compression;
 #[cfg(feature = "serialize")]
 pub mod de;
 mod errors;
 mod escapei;
 pub mod escape {
     //! Manage xml character escapes
-    pub(crate) use crate::escapei::{do_unescape, EscapeError};
-    pub use crate::escapei::{escape, partial_escape, unescape, unescape_with};
+    pub(crate) use crate::escapei::{do_unescape, unescape_str_to, EscapeError};
+    pub use crate::escapei::{
+        entities, escape, escape_with, partial_escape, unescape, unescape_to, unescape_with,
+        Entities, EntityKind, EntityRef,
+    };
 }
 pub mod events;
 pub mod name;
-mod reader;
+mod push_parser;
+pub mod reader;
 #[cfg(feature = "serialize")]
 pub mod se;
+mod str_reader;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "tree")]
+pub mod tree;
 /// Not an official API, public for integration tests
 #[doc(hidden)]
 pub mod utils;
 mod writer;
 
 // reexports
+pub use crate::canonical::canonicalize;
+#[cfg(feature = "compression")]
+pub use crate::compression::Decompressor;
 #[cfg(feature = "serialize")]
 pub use crate::errors::serialize::DeError;
 pub use crate::errors::{Error, Result};
-pub use crate::reader::{Decoder, Reader};
-pub use crate::writer::{ElementWriter, Writer};
+pub use crate::push_parser::{PushBuffer, PushEvents, PushParser};
+pub use crate::reader::{root_element_name, Decoder, Reader};
+pub use crate::str_reader::{StrEvent, StrReader};
+pub use crate::writer::{ElementWriter, EventFormatter, Writer};
@@ -9,7 +9,8 @@ use crate::events::BytesStart;
 use crate::utils::write_byte_string;
 use memchr::memchr;
 use std::convert::TryFrom;
-use std::fmt::{self, Debug, Formatter};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::from_utf8;
 
 /// A [qualified name] of an element or an attribute, including an optional
 /// namespace [prefix](Prefix) and a [local name](LocalName).
@@ -25,6 +26,22 @@ impl<'a> QName<'a> {
         self.0
     }
 
+    /// Returns this name as a string slice or an error, if the name is not
+    /// a valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use quick_xml::name::QName;
+    /// let qname = QName(b"prefix:name");
+    /// assert_eq!(qname.as_str().unwrap(), "prefix:name");
+    /// assert!(QName(b"\xFF").as_str().is_err());
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Result<&'a str> {
+        Ok(from_utf8(self.0)?)
+    }
+
     /// Returns local part of this qualified name.
     ///
     /// All content up to and including the first `:` character is removed from
@@ -119,6 +136,18 @@ impl<'a> Debug for QName<'a> {
         write!(f, ")")
     }
 }
+impl<'a> Display for QName<'a> {
+    /// Writes the name, replacing invalid UTF-8 sequences with U+FFFD
+    ///
+    /// ```
+    /// # use quick_xml::name::QName;
+    /// assert_eq!(format!("<{}>", QName(b"prefix:name")), "<prefix:name>");
+    /// assert_eq!(format!("[{:>8}]", QName(b"a:b")), "[     a:b]");
+    /// ```
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad(&String::from_utf8_lossy(self.0))
+    }
+}
 impl<'a> AsRef<[u8]> for QName<'a> {
     #[inline]
     fn as_ref(&self) -> &[u8] {
@@ -141,6 +170,24 @@ impl<'a> LocalName<'a> {
     pub fn into_inner(self) -> &'a [u8] {
         self.0
     }
+
+    /// Returns this name as a string slice or an error, if the name is not
+    /// a valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use quick_xml::name::QName;
+    /// let name = QName(b"prefix:name").local_name();
+    /// match name.as_str().unwrap() {
+    ///     "name" => {}
+    ///     other => panic!("Unexpected name `{}`", other),
+    /// }
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Result<&'a str> {
+        Ok(from_utf8(self.0)?)
+    }
 }
 impl<'a> Debug for LocalName<'a> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
@@ -149,6 +196,17 @@ impl<'a> Debug for LocalName<'a> {
         write!(f, ")")
     }
 }
+impl<'a> Display for LocalName<'a> {
+    /// Writes the name, replacing invalid UTF-8 sequences with U+FFFD
+    ///
+    /// ```
+    /// # use quick_xml::name::QName;
+    /// assert_eq!(QName(b"prefix:name").local_name().to_string(), "name");
+    /// ```
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad(&String::from_utf8_lossy(self.0))
+    }
+}
 impl<'a> AsRef<[u8]> for LocalName<'a> {
     #[inline]
     fn as_ref(&self) -> &[u8] {
@@ -291,6 +349,74 @@ pub enum ResolveResult<'ns> {
     /// Specified prefix was not found in scope
     Unknown(Vec<u8>),
 }
+
+impl<'ns> ResolveResult<'ns> {
+    /// Returns `true` if the name is bound to a namespace.
+    #[inline]
+    pub fn is_bound(&self) -> bool {
+        matches!(self, Self::Bound(_))
+    }
+
+    /// Returns `true` if the name is not bound to any namespace.
+    #[inline]
+    pub fn is_unbound(&self) -> bool {
+        matches!(self, Self::Unbound)
+    }
+
+    /// Returns `true` if the name uses a prefix that was not declared.
+    #[inline]
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_))
+    }
+
+    /// Returns the namespace name (URI) if the name is [bound] to it, or `None`
+    /// otherwise.
+    ///
+    /// [bound]: Self::Bound
+    #[inline]
+    pub fn bound(&self) -> Option<&'ns [u8]> {
+        match self {
+            Self::Bound(ns) => Some(ns.into_inner()),
+            _ => None,
+        }
+    }
+
+    /// Returns the undeclared prefix if the prefix is [unknown], or `None`
+    /// otherwise.
+    ///
+    /// [unknown]: Self::Unknown
+    #[inline]
+    pub fn unknown(&self) -> Option<&[u8]> {
+        match self {
+            Self::Unknown(prefix) => Some(prefix),
+            _ => None,
+        }
+    }
+
+    /// Returns the namespace name (URI) if the name is [bound] to it, or
+    /// `default` otherwise. Note, that `default` is returned for [unknown]
+    /// prefixes too, use [`TryFrom`] conversion to `Option<Namespace>` if
+    /// they should be treated as errors.
+    ///
+    /// This is a shortcut for a common pattern of treating unbound names as
+    /// names in the empty namespace:
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::name::{Namespace, ResolveResult};
+    ///
+    /// assert_eq!(ResolveResult::Bound(Namespace(b"urn:example")).unwrap_or(b""), b"urn:example");
+    /// assert_eq!(ResolveResult::Unbound.unwrap_or(b""), b"");
+    /// ```
+    ///
+    /// [bound]: Self::Bound
+    /// [unknown]: Self::Unknown
+    #[inline]
+    pub fn unwrap_or(&self, default: &'ns [u8]) -> &'ns [u8] {
+        self.bound().unwrap_or(default)
+    }
+}
+
 impl<'ns> Debug for ResolveResult<'ns> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
@@ -385,6 +511,19 @@ impl NamespaceEntry {
     }
 }
 
+/// A saved state of the namespace scope, created by [`Reader::namespace_checkpoint()`].
+///
+/// [`Reader::namespace_checkpoint()`]: crate::Reader::namespace_checkpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolverCheckpoint {
+    /// Number of namespace bindings in scope
+    bindings_len: usize,
+    /// Length of the used part of the namespace buffer
+    buffer_len: usize,
+    /// Nesting level of the resolver
+    nesting_level: i32,
+}
+
 /// A namespace management buffer.
 ///
 /// Holds all internal logic to push/pop namespaces with their levels.
@@ -403,33 +542,15 @@ impl NamespaceResolver {
     ///
     /// [namespace binding]: https://www.w3.org/TR/xml-names11/#dt-NSDecl
     pub fn push(&mut self, start: &BytesStart, buffer: &mut Vec<u8>) {
-        self.nesting_level += 1;
-        let level = self.nesting_level;
+        self.push_scope();
         // adds new namespaces for attributes starting with 'xmlns:' and for the 'xmlns'
         // (default namespace) attribute.
         for a in start.attributes().with_checks(false) {
             if let Ok(Attribute { key: k, value: v }) = a {
                 match k.as_namespace_binding() {
-                    Some(PrefixDeclaration::Default) => {
-                        let start = buffer.len();
-                        buffer.extend_from_slice(&*v);
-                        self.bindings.push(NamespaceEntry {
-                            start,
-                            prefix_len: 0,
-                            value_len: v.len(),
-                            level,
-                        });
-                    }
+                    Some(PrefixDeclaration::Default) => self.declare(None, &v, buffer),
                     Some(PrefixDeclaration::Named(prefix)) => {
-                        let start = buffer.len();
-                        buffer.extend_from_slice(prefix);
-                        buffer.extend_from_slice(&*v);
-                        self.bindings.push(NamespaceEntry {
-                            start,
-                            prefix_len: prefix.len(),
-                            value_len: v.len(),
-                            level,
-                        });
+                        self.declare(Some(prefix), &v, buffer)
                     }
                     None => {}
                 }
@@ -439,6 +560,32 @@ impl NamespaceResolver {
         }
     }
 
+    /// Begins a new scope without any [namespace bindings]. Bindings can be
+    /// added to it with [`Self::declare()`].
+    ///
+    /// [namespace bindings]: https://www.w3.org/TR/xml-names11/#dt-NSDecl
+    pub fn push_scope(&mut self) {
+        self.nesting_level += 1;
+    }
+
+    /// Adds a [namespace binding] of the `prefix` (`None` for the default
+    /// namespace) to the top-most scope. An empty `namespace` resets
+    /// the binding, as `xmlns:prefix=""` does.
+    ///
+    /// [namespace binding]: https://www.w3.org/TR/xml-names11/#dt-NSDecl
+    pub fn declare(&mut self, prefix: Option<&[u8]>, namespace: &[u8], buffer: &mut Vec<u8>) {
+        let start = buffer.len();
+        let prefix = prefix.unwrap_or_default();
+        buffer.extend_from_slice(prefix);
+        buffer.extend_from_slice(namespace);
+        self.bindings.push(NamespaceEntry {
+            start,
+            prefix_len: prefix.len(),
+            value_len: namespace.len(),
+            level: self.nesting_level,
+        });
+    }
+
     /// Ends a top-most scope by popping all [namespace binding], that was added by
     /// last call to [`Self::push()`].
     ///
@@ -463,6 +610,59 @@ impl NamespaceResolver {
         }
     }
 
+    /// Returns all [namespace bindings] of the top-most scope, that is, bindings
+    /// that were added by the last call to [`Self::push()`] which scope was not
+    /// popped yet, as pairs of prefix (`None` for the default namespace) and
+    /// namespace name, in the order of declaration.
+    ///
+    /// [namespace bindings]: https://www.w3.org/TR/xml-names11/#dt-NSDecl
+    pub fn current_scope<'a, 'ns: 'a>(
+        &'a self,
+        buffer: &'ns [u8],
+    ) -> impl Iterator<Item = (Option<&'ns [u8]>, &'ns [u8])> + 'a {
+        let level = self.nesting_level;
+        let start = self
+            .bindings
+            .iter()
+            .rposition(|n| n.level < level)
+            .map_or(0, |p| p + 1);
+        self.bindings[start..].iter().map(move |n| {
+            let start = n.start + n.prefix_len;
+            (
+                n.prefix(buffer).map(Prefix::into_inner),
+                &buffer[start..start + n.value_len],
+            )
+        })
+    }
+
+    /// Records the current state of the resolver, which can be restored later
+    /// by [`Self::restore()`].
+    pub fn checkpoint(&self) -> ResolverCheckpoint {
+        ResolverCheckpoint {
+            bindings_len: self.bindings.len(),
+            buffer_len: self
+                .bindings
+                .last()
+                .map_or(0, |n| n.start + n.prefix_len + n.value_len),
+            nesting_level: self.nesting_level,
+        }
+    }
+
+    /// Rolls back all namespace bindings that were added after the `checkpoint`
+    /// was created.
+    ///
+    /// Bindings, that was in scope at the moment of checkpoint creation, should
+    /// not be popped since then, because they cannot be restored.
+    pub fn restore(&mut self, checkpoint: ResolverCheckpoint, buffer: &mut Vec<u8>) {
+        debug_assert!(
+            self.bindings.len() >= checkpoint.bindings_len,
+            "namespace bindings from the checkpoint were popped"
+        );
+        self.bindings.truncate(checkpoint.bindings_len);
+        buffer.truncate(checkpoint.buffer_len);
+        self.nesting_level = checkpoint.nesting_level;
+    }
+
     /// Resolves a potentially qualified **element name** or **attribute name**
     /// into (namespace name, local name).
     ///
@@ -797,4 +997,23 @@ mod namespaces {
         );
         assert_eq!(resolver.find(name, &buffer), Unknown(b"unknown".to_vec()));
     }
+
+    #[test]
+    fn resolve_result_accessors() {
+        let bound = Bound(Namespace(b"urn:example"));
+        assert!(bound.is_bound());
+        assert_eq!(bound.bound(), Some(&b"urn:example"[..]));
+        assert_eq!(bound.unknown(), None);
+        assert_eq!(bound.unwrap_or(b"default"), b"urn:example");
+
+        assert!(Unbound.is_unbound());
+        assert_eq!(Unbound.bound(), None);
+        assert_eq!(Unbound.unwrap_or(b"default"), b"default");
+
+        let unknown = Unknown(b"p".to_vec());
+        assert!(unknown.is_unknown());
+        assert!(!unknown.is_bound());
+        assert_eq!(unknown.unknown(), Some(&b"p"[..]));
+        assert_eq!(unknown.unwrap_or(b"default"), b"default");
+    }
 }
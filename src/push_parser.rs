@@ -0,0 +1,641 @@
+//! A push parser, that accepts the input in chunks and returns only complete events
+
+use std::io::{self, BufRead, Read};
+
+use crate::errors::Result;
+use crate::events::Event;
+use crate::reader::{is_whitespace, DtdParser, ReadElementState, ReadState, Reader};
+
+/// The input of a [`PushParser`]: all bytes that were fed, but not yet parsed
+/// into complete events.
+///
+/// This type is used only as a type parameter of the [`Reader`], returned by
+/// [`PushParser::get_ref`] and [`PushParser::get_mut`].
+#[derive(Clone, Debug, Default)]
+pub struct PushBuffer {
+    /// Bytes fed into the parser. Bytes of already returned events are removed
+    /// from time to time, when they occupy more than a half of the buffer
+    data: Vec<u8>,
+    /// Position of the next byte to read in `data`
+    pos: usize,
+    /// `true` if the reader asked for bytes after the end of `data`, that is,
+    /// the current event can be incomplete
+    exhausted: bool,
+}
+
+impl Read for PushBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for PushBuffer {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.data.len() {
+            self.exhausted = true;
+        }
+        Ok(&self.data[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.data.len());
+    }
+}
+
+/// A parser for XML, that arrives in chunks, for example, from the network.
+///
+/// Instead of pulling the data from a source, as [`Reader`] does, the input is
+/// pushed into the parser with [`feed`]. Each call returns an iterator over
+/// events that could be parsed from all the input fed so far. Incomplete
+/// markup or text at the end of the input is kept in the internal buffer until
+/// more data arrives, so an event is returned only when it is complete, even
+/// if it spans many chunks. When the input is over, call [`finish`] to get the
+/// remaining events and the final [`Event::Eof`].
+///
+/// Events are returned as owned, because their content can be collected from
+/// several chunks. Events that were not taken from the iterator are not lost,
+/// they are returned by the next call to [`feed`] or [`finish`].
+///
+/// The underlying [`Reader`] is available with [`get_ref`] and [`get_mut`] and
+/// can be configured as usual. Do not read events from it directly.
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+/// use quick_xml::PushParser;
+///
+/// let mut parser = PushParser::new();
+///
+/// let events: Vec<_> = parser.feed(b"<tag attr='va").collect();
+/// assert!(events.is_empty());
+///
+/// let events: Vec<_> = parser.feed(b"lue'>te").map(Result::unwrap).collect();
+/// assert_eq!(events, vec![Event::Start(BytesStart::borrowed(b"tag attr='value'", 3))]);
+///
+/// let events: Vec<_> = parser.feed(b"xt</tag>").map(Result::unwrap).collect();
+/// assert_eq!(
+///     events,
+///     vec![
+///         Event::Text(BytesText::from_escaped_str("text")),
+///         Event::End(BytesEnd::borrowed(b"tag")),
+///     ]
+/// );
+///
+/// let events: Vec<_> = parser.finish().map(Result::unwrap).collect();
+/// assert_eq!(events, vec![Event::Eof]);
+/// ```
+///
+/// [`feed`]: Self::feed
+/// [`finish`]: Self::finish
+/// [`get_ref`]: Self::get_ref
+/// [`get_mut`]: Self::get_mut
+#[derive(Clone)]
+pub struct PushParser {
+    reader: Reader<PushBuffer>,
+    /// Buffer for the content of events, reused between reads
+    buf: Vec<u8>,
+    /// `true` if no more input is expected
+    finished: bool,
+    /// `true` if the `Eof` event was returned
+    eof: bool,
+    /// Set if the last event was incomplete, to not parse it again until the new
+    /// input can complete it
+    incomplete: Option<Incomplete>,
+}
+
+/// Describes an event, which could not be read because the input was over
+#[derive(Clone, Debug)]
+struct Incomplete {
+    /// Number of bytes in the input, that were available for the event
+    len: usize,
+    /// What can end the event
+    end: EventEnd,
+    /// Position in the input, from which the search of the end continues
+    scanned: usize,
+}
+
+impl Incomplete {
+    /// Returns `true` if the new input can complete the event. The input is
+    /// searched for the end of the event only once, continuing from the position
+    /// where the previous search stopped.
+    ///
+    /// Otherwise the event is parsed again only when the amount of its input
+    /// is doubled, so errors, that do not need the end of the event, such as
+    /// exceeding of [`Reader::max_event_size`], are still reported, but each byte
+    /// of the event is parsed only a few times in total
+    fn can_complete(&mut self, consumed: &[u8], source: &PushBuffer) -> bool {
+        if let EventEnd::Unknown = self.end {
+            let (end, start) = EventEnd::new(consumed, &source.data[source.pos..]);
+            // The search depends on the kind of the event, so bytes, that were
+            // available for the last read, are searched again
+            if !matches!(end, EventEnd::Any) {
+                self.scanned = source.pos + start;
+            }
+            self.end = end;
+        }
+        let new = &source.data[self.scanned..];
+        self.scanned = source.data.len();
+
+        let found = match &mut self.end {
+            EventEnd::Unknown => false,
+            EventEnd::Any => memchr::memchr2(b'<', b'>', new).is_some(),
+            EventEnd::Text => memchr::memchr(b'<', new).is_some(),
+            EventEnd::EndTag => memchr::memchr(b'>', new).is_some(),
+            EventEnd::Element(state) => state.change(new).is_some(),
+            EventEnd::DocType(parser) => parser.feed(new).is_some(),
+            EventEnd::Sequence { byte, count, seen } => {
+                let (byte, count) = (*byte, *count);
+                let found = memchr::memchr_iter(b'>', new).any(|i| {
+                    let run = trailing(&new[..i], byte, count);
+                    run == count || run == i && *seen + i >= count
+                });
+                // The end sequence could be split between chunks
+                let run = trailing(new, byte, count);
+                *seen = if run == new.len() {
+                    (*seen + run).min(count)
+                } else {
+                    run
+                };
+                found
+            }
+        };
+        found || source.data.len() - source.pos >= 2 * self.len
+    }
+}
+
+/// Returns the number of `byte`s at the end of `bytes`, but no more than `max`
+fn trailing(bytes: &[u8], byte: u8, max: usize) -> usize {
+    bytes
+        .iter()
+        .rev()
+        .take(max)
+        .take_while(|&&b| b == byte)
+        .count()
+}
+
+/// What can end an incomplete event
+#[derive(Clone, Debug)]
+enum EventEnd {
+    /// The input is too short to know the kind of the event
+    Unknown,
+    /// The input is not in an ASCII-compatible encoding, so the kind of the event
+    /// is unknown and any `<` or `>` can end it
+    Any,
+    /// A text, which ends before `<`
+    Text,
+    /// An end tag, which ends at `>`
+    EndTag,
+    /// A start or an empty element tag, which ends at `>` outside of attribute values
+    Element(ReadElementState),
+    /// A DOCTYPE declaration
+    DocType(DtdParser),
+    /// A comment, a CDATA section or a processing instruction, which ends with
+    /// `count` bytes `byte` followed by `>`
+    Sequence {
+        byte: u8,
+        count: usize,
+        /// Number of `byte`s at the end of the already searched input
+        seen: usize,
+    },
+}
+
+impl EventEnd {
+    /// Determines what can end an event, which starts with the `consumed` bytes
+    /// (see [`Reader::consumed_markup`]) followed by the `input`. Returns it
+    /// together with the offset in the `input`, from which the end should be
+    /// searched
+    fn new(consumed: &[u8], input: &[u8]) -> (Self, usize) {
+        // Whitespaces before a markup can be trimmed, but not inside it
+        let start = if consumed.is_empty() {
+            match input.iter().position(|&b| !is_whitespace(b)) {
+                Some(start) => start,
+                None => return (Self::Unknown, 0),
+            }
+        } else {
+            0
+        };
+        let mut head = consumed.iter().chain(&input[start..]).copied();
+        let sequence = |byte, count| Self::Sequence {
+            byte,
+            count,
+            seen: trailing(consumed, byte, count),
+        };
+        let end = match (head.next(), head.next(), head.next()) {
+            // UTF-16 or the BOM at the start of the document
+            (Some(0), _, _) | (Some(0xEF), _, _) | (Some(0xFE), _, _) | (Some(0xFF), _, _) => {
+                Self::Any
+            }
+            (Some(b'<'), Some(0), _) => Self::Any,
+            (Some(b'<'), None, _) | (Some(b'<'), Some(b'!'), None) => Self::Unknown,
+            (Some(b'<'), Some(b'/'), _) => Self::EndTag,
+            (Some(b'<'), Some(b'?'), _) => sequence(b'?', 1),
+            (Some(b'<'), Some(b'!'), Some(b'-')) => sequence(b'-', 2),
+            (Some(b'<'), Some(b'!'), Some(b'[')) => sequence(b']', 2),
+            (Some(b'<'), Some(b'!'), _) => Self::DocType(DtdParser::default()),
+            (Some(b'<'), _, _) => Self::Element(ReadElementState::Elem),
+            _ => Self::Text,
+        };
+        if consumed.is_empty() {
+            // Skip the first byte, which is either `<` or a part of a text
+            (end, start + 1)
+        } else {
+            (end, 0)
+        }
+    }
+
+    /// Returns `true` if the event can be an end tag, that closes the innermost
+    /// opened element
+    fn can_close(&self) -> bool {
+        matches!(self, Self::Unknown | Self::Any | Self::EndTag)
+    }
+}
+
+impl PushParser {
+    /// Creates a parser without any input.
+    pub fn new() -> Self {
+        Self {
+            reader: Reader::from_reader(PushBuffer::default()),
+            buf: Vec::new(),
+            finished: false,
+            eof: false,
+            incomplete: None,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &Reader<PushBuffer> {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader, for example,
+    /// to change its settings.
+    pub fn get_mut(&mut self) -> &mut Reader<PushBuffer> {
+        &mut self.reader
+    }
+
+    /// Appends a chunk of the input and returns an iterator over all complete
+    /// events that can be parsed now.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called after [`finish`](Self::finish).
+    pub fn feed(&mut self, bytes: &[u8]) -> PushEvents<'_> {
+        assert!(!self.finished, "`feed` called after `finish`");
+        self.reader.get_mut().data.extend_from_slice(bytes);
+        PushEvents { parser: self }
+    }
+
+    /// Signals the end of the input and returns an iterator over all remaining
+    /// events, including the final [`Event::Eof`].
+    ///
+    /// Incomplete markup at the end of the input is handled the same way as
+    /// [`Reader`] does it, usually it is reported as an error.
+    pub fn finish(&mut self) -> PushEvents<'_> {
+        self.finished = true;
+        PushEvents { parser: self }
+    }
+
+    /// Parses the next event. Returns `Ok(None)` if more input is needed to
+    /// complete the event, or if the `Eof` event was already returned
+    fn next_event(&mut self) -> Result<Option<Event<'static>>> {
+        if self.eof {
+            return Ok(None);
+        }
+        loop {
+            // Events are read one by one rather than with `read_event_into`,
+            // which skips events, so each complete event can be committed
+            let event = match self.read_complete_event()? {
+                Some(event) if self.reader.is_skipped(&event) => continue,
+                event => event,
+            };
+            return match event {
+                // The reader returns `Eof` after an error, but the input is not
+                // over until `finish` is called
+                Some(Event::Eof) if !self.finished => Ok(None),
+                Some(Event::Eof) => {
+                    self.eof = true;
+                    Ok(Some(Event::Eof))
+                }
+                event => Ok(event),
+            };
+        }
+    }
+
+    /// Reads the next event. Returns `Ok(None)` and returns the reader to the
+    /// start of the event if it is incomplete
+    fn read_complete_event(&mut self) -> Result<Option<Event<'static>>> {
+        // The reader changes its state while reading, so remember it to be
+        // able to return to the start of the event if it is incomplete
+        let state = if self.finished {
+            None
+        } else {
+            let consumed = self.reader.consumed_markup();
+            let source = self.reader.get_ref();
+            if let Some(incomplete) = &mut self.incomplete {
+                if !incomplete.can_complete(consumed, source) {
+                    return Ok(None);
+                }
+            }
+            let end_tag = EventEnd::new(consumed, &source.data[source.pos..])
+                .0
+                .can_close();
+            Some((self.reader.save_read_state(end_tag), source.pos))
+        };
+
+        self.buf.clear();
+        let result = self
+            .reader
+            .read_any_event_into(&mut self.buf)
+            .map(Event::into_owned);
+
+        if let Some((state, pos)) = state {
+            if self.reader.get_ref().exhausted {
+                self.restore(state, pos);
+                return Ok(None);
+            }
+        }
+        self.incomplete = None;
+        // Bytes of the returned event are not needed anymore. Remove them only
+        // when they occupy more than a half of the buffer, so each byte is moved
+        // only a few times in total
+        let source = self.reader.get_mut();
+        if source.pos > source.data.len() / 2 {
+            source.data.drain(..source.pos);
+            source.pos = 0;
+        }
+
+        result.map(Some)
+    }
+
+    /// Returns the reader to the start of an incomplete event, which begins
+    /// at `pos` in the input
+    fn restore(&mut self, state: ReadState, pos: usize) {
+        self.reader.restore_read_state(state);
+        let source = self.reader.get_mut();
+        source.pos = pos;
+        source.exhausted = false;
+        self.incomplete = Some(Incomplete {
+            len: source.data.len() - pos,
+            end: EventEnd::Unknown,
+            scanned: source.data.len(),
+        });
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An iterator over complete events of a [`PushParser`], returned by
+/// [`PushParser::feed`] and [`PushParser::finish`].
+pub struct PushEvents<'p> {
+    parser: &'p mut PushParser,
+}
+
+impl<'p> Iterator for PushEvents<'p> {
+    type Item = Result<Event<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parser.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Error;
+    use crate::events::{BytesCData, BytesEnd, BytesStart, BytesText};
+    use crate::reader::EventMask;
+    use pretty_assertions::assert_eq;
+
+    const XML: &[u8] = b"<?xml version='1.0'?>\
+        <root a='1' b=\"x>y\">text &amp; more<!-- comment --><![CDATA[<data>]]>\
+        <empty/><?pi?><child>  </child></root>";
+
+    /// Reads all events from `XML` at once
+    fn expected() -> Vec<Event<'static>> {
+        let mut reader = Reader::from_reader(XML);
+        let mut buf = Vec::new();
+        let mut events = Vec::new();
+        loop {
+            let event = reader.read_event_into(&mut buf).unwrap().into_owned();
+            let eof = event == Event::Eof;
+            events.push(event);
+            if eof {
+                return events;
+            }
+        }
+    }
+
+    /// An event that spans chunks should be returned only when it is complete,
+    /// regardless of where the input was split
+    #[test]
+    fn chunks() {
+        let expected = expected();
+        for size in 1..XML.len() {
+            let mut parser = PushParser::new();
+            let mut events = Vec::new();
+            for chunk in XML.chunks(size) {
+                events.extend(parser.feed(chunk).map(Result::unwrap));
+            }
+            events.extend(parser.finish().map(Result::unwrap));
+            assert_eq!(events, expected, "chunk size: {}", size);
+        }
+    }
+
+    /// Attributes, read from the input one at a time, should be returned only
+    /// when they are complete, and the element should be opened once
+    #[test]
+    fn streamed_attributes_chunks() {
+        let xml = b"<a x='1' y = \"2\"><b z='3'/></a>";
+        let mut reader = Reader::from_reader(xml as &[u8]);
+        reader.stream_attributes(true);
+        let mut buf = Vec::new();
+        let mut expected = Vec::new();
+        loop {
+            let event = reader.read_event_into(&mut buf).unwrap().into_owned();
+            let eof = event == Event::Eof;
+            expected.push(event);
+            if eof {
+                break;
+            }
+        }
+        for size in 1..xml.len() {
+            let mut parser = PushParser::new();
+            parser.get_mut().stream_attributes(true);
+            let mut events = Vec::new();
+            for chunk in xml.chunks(size) {
+                events.extend(parser.feed(chunk).map(Result::unwrap));
+            }
+            events.extend(parser.finish().map(Result::unwrap));
+            assert_eq!(events, expected, "chunk size: {}", size);
+        }
+    }
+
+    /// Each byte of the input should be parsed only a few times, so big
+    /// documents, fed in small chunks, are parsed in a linear time
+    #[test]
+    fn large() {
+        let mut xml = b"<root>".to_vec();
+        for i in 0..50_000 {
+            xml.extend_from_slice(format!("<item id='{}'>text {}</item>", i, i).as_bytes());
+        }
+        // Events, that span many chunks
+        xml.extend_from_slice(b"<!--");
+        xml.resize(xml.len() + 1_000_000, b'-');
+        xml.extend_from_slice(b"-><!--");
+        for _ in 0..50_000 {
+            xml.extend_from_slice(b"<a>b</a>-<![CDATA[");
+        }
+        xml.extend_from_slice(b"--><![CDATA[");
+        for _ in 0..50_000 {
+            xml.extend_from_slice(b"<a>b</a>]]");
+        }
+        xml.extend_from_slice(b"]]><a b='");
+        for _ in 0..50_000 {
+            xml.extend_from_slice(b"<a>b</a>\"");
+        }
+        xml.extend_from_slice(b"'/>");
+        xml.resize(xml.len() + 1_000_000, b'x');
+        xml.extend_from_slice(b"</root>");
+
+        let mut reader = Reader::from_reader(xml.as_slice());
+        let mut buf = Vec::new();
+        let mut expected = Vec::new();
+        loop {
+            let event = reader.read_event_into(&mut buf).unwrap().into_owned();
+            let eof = event == Event::Eof;
+            expected.push(event);
+            if eof {
+                break;
+            }
+        }
+
+        let mut parser = PushParser::new();
+        let mut events = Vec::new();
+        for chunk in xml.chunks(16) {
+            events.extend(parser.feed(chunk).map(Result::unwrap));
+        }
+        events.extend(parser.finish().map(Result::unwrap));
+        assert!(xml.len() > 4_000_000);
+        assert_eq!(events.len(), expected.len());
+        assert!(events == expected);
+    }
+
+    /// Parts of a CDATA section should be returned only when they are complete,
+    /// even if the end of the section is split between chunks
+    #[test]
+    fn cdata_chunks() {
+        /// Reads all events, joining parts of CDATA sections, because the
+        /// boundaries of the parts depend on the available input
+        fn join(events: impl Iterator<Item = Event<'static>>) -> Vec<Event<'static>> {
+            let mut joined: Vec<Event<'static>> = Vec::new();
+            for event in events {
+                match (joined.last_mut(), event) {
+                    (Some(Event::CData(last)), Event::CData(part)) => {
+                        let content = [&**last, &*part].concat();
+                        *last = BytesCData::new(content);
+                    }
+                    (_, event) => joined.push(event),
+                }
+            }
+            joined
+        }
+
+        let xml = b"<a><![CDATA[x]]]]><![CDATA[]]]>y<![CDATA[z]]]]]]></a>";
+        let mut reader = Reader::from_reader(xml.as_ref());
+        let mut buf = Vec::new();
+        let expected = join(std::iter::from_fn(|| {
+            match reader.read_event_into(&mut buf).unwrap() {
+                Event::Eof => None,
+                event => Some(event.into_owned()),
+            }
+        }));
+
+        for size in 1..xml.len() {
+            let mut parser = PushParser::new();
+            parser.get_mut().cdata_chunk_size(Some(3));
+            let mut events = Vec::new();
+            for chunk in xml.chunks(size) {
+                events.extend(parser.feed(chunk).map(Result::unwrap));
+            }
+            events.extend(parser.finish().map(Result::unwrap));
+            assert_eq!(events.pop(), Some(Event::Eof));
+            assert_eq!(join(events.into_iter()), expected, "chunk size: {}", size);
+        }
+    }
+
+    /// Settings of the reader should be respected
+    #[test]
+    fn trim_text() {
+        let mut parser = PushParser::new();
+        parser.get_mut().trim_text(true);
+
+        let events: Vec<_> = parser.feed(b"<a>  ").map(Result::unwrap).collect();
+        assert_eq!(events, vec![Event::Start(BytesStart::borrowed_name(b"a"))]);
+        // Whitespaces at the end of the chunk could be followed by the text
+        assert_eq!(parser.feed(b"  te").count(), 0);
+        let events: Vec<_> = parser.feed(b"xt  </a>").map(Result::unwrap).collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::Text(BytesText::from_escaped_str("text")),
+                Event::End(BytesEnd::borrowed(b"a")),
+            ]
+        );
+    }
+
+    /// Skipped events should not be returned, even if they span chunks
+    #[test]
+    fn skip_events() {
+        let mut parser = PushParser::new();
+        parser.get_mut().skip_events(EventMask::COMMENT);
+
+        assert_eq!(parser.feed(b"<a><!-- com").count(), 1);
+        let events: Vec<_> = parser.feed(b"ment --></a>").map(Result::unwrap).collect();
+        assert_eq!(events, vec![Event::End(BytesEnd::borrowed(b"a"))]);
+    }
+
+    /// Events not taken from the iterator should be returned later
+    #[test]
+    fn not_consumed() {
+        let mut parser = PushParser::new();
+        assert_eq!(
+            parser.feed(b"<a/><b/>").next().unwrap().unwrap(),
+            Event::Empty(BytesStart::borrowed_name(b"a"))
+        );
+        let events: Vec<_> = parser.finish().map(Result::unwrap).collect();
+        assert_eq!(
+            events,
+            vec![Event::Empty(BytesStart::borrowed_name(b"b")), Event::Eof]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        // Incomplete markup is reported only after the end of input
+        let mut parser = PushParser::new();
+        assert_eq!(parser.feed(b"<a/><!-- a").count(), 1);
+        let mut events = parser.finish();
+        assert!(matches!(
+            events.next(),
+            Some(Err(Error::UnexpectedEof(_, _)))
+        ));
+
+        // Errors in complete markup are reported immediately
+        let mut parser = PushParser::new();
+        let mut events = parser.feed(b"<a></b>");
+        assert!(matches!(events.next(), Some(Ok(Event::Start(_)))));
+        assert!(matches!(
+            events.next(),
+            Some(Err(Error::EndEventMismatch { .. }))
+        ));
+        assert!(events.next().is_none());
+    }
+}
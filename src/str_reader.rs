@@ -0,0 +1,241 @@
+//! A reader over a string slice, that returns events with string content
+
+use std::borrow::Cow;
+use std::str::from_utf8;
+
+use crate::errors::Result;
+use crate::events::attributes::Attr;
+use crate::events::{BytesDecl, BytesStart, Event};
+use crate::reader::Reader;
+
+/// An event, returned by the [`StrReader`].
+///
+/// This is the same as [`Event`], but textual content is provided as strings
+/// instead of bytes. [`Start`](Self::Start), [`Empty`](Self::Empty) and
+/// [`Decl`](Self::Decl) events are the same as in [`Event`], use
+/// [`BytesStart::attributes_decoded`] to get values of attributes as strings.
+///
+/// Content of the events is *not* unescaped, as in [`Event`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StrEvent<'a> {
+    /// Text before the first markup, see [`Event::StartText`]
+    StartText(Cow<'a, str>),
+    /// Start tag (with attributes) `<tag attr="value">`.
+    Start(BytesStart<'a>),
+    /// Name of the end tag `</tag>`.
+    End(Cow<'a, str>),
+    /// Empty element tag (with attributes) `<tag attr="value" />`.
+    Empty(BytesStart<'a>),
+    /// Escaped character data between `Start` and `End` element.
+    Text(Cow<'a, str>),
+    /// Comment `<!-- ... -->`.
+    Comment(Cow<'a, str>),
+    /// CData `<![CDATA[...]]>`.
+    CData(Cow<'a, str>),
+    /// XML declaration `<?xml ...?>`.
+    Decl(BytesDecl<'a>),
+    /// Processing instruction `<?...?>`.
+    PI(Cow<'a, str>),
+    /// Doctype `<!DOCTYPE ...>`.
+    DocType(Cow<'a, str>),
+    /// Name and value of an attribute of the last `Start` or `Empty` element,
+    /// see [`Event::Attribute`]. The value is empty for HTML-style attributes
+    /// without a value.
+    Attribute(Cow<'a, str>, Cow<'a, str>),
+    /// Generated after the last [`StrEvent::Attribute`] of an element.
+    AttributesEnd,
+    /// End of XML document.
+    Eof,
+}
+
+/// A reader over a string slice, that returns events with string content.
+///
+/// Because the input is a valid UTF-8 string, the content of events that is
+/// borrowed from the input is returned as string slices without validation.
+/// Only content, that was changed by the reader, for example, by a
+/// [name mapping], is validated.
+///
+/// The underlying [`Reader`] is available with [`get_ref()`] and
+/// [`get_mut()`] and can be configured as usual.
+///
+/// ```
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::{StrEvent, StrReader};
+///
+/// let mut reader = StrReader::new("<tag>текст</tag>");
+/// reader.get_mut().trim_text(true);
+///
+/// let mut texts = Vec::new();
+/// loop {
+///     match reader.read_event().unwrap() {
+///         StrEvent::Text(text) => texts.push(text),
+///         StrEvent::Eof => break,
+///         _ => {}
+///     }
+/// }
+/// assert_eq!(texts, vec!["текст"]);
+/// ```
+///
+/// [name mapping]: Reader::map_names
+/// [`get_ref()`]: Self::get_ref
+/// [`get_mut()`]: Self::get_mut
+#[derive(Clone)]
+pub struct StrReader<'a> {
+    reader: Reader<&'a [u8]>,
+    input: &'a str,
+}
+
+impl<'a> StrReader<'a> {
+    /// Creates a reader over a string slice.
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            reader: Reader::from_str(input),
+            input,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &Reader<&'a [u8]> {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader, for example,
+    /// to change its settings.
+    pub fn get_mut(&mut self) -> &mut Reader<&'a [u8]> {
+        &mut self.reader
+    }
+
+    /// Consumes `StrReader` returning the underlying reader.
+    pub fn into_inner(self) -> Reader<&'a [u8]> {
+        self.reader
+    }
+
+    /// Reads the next event.
+    ///
+    /// This is the same as [`Reader::read_event`], but returns a [`StrEvent`].
+    pub fn read_event(&mut self) -> Result<StrEvent<'a>> {
+        Ok(match self.reader.read_event()? {
+            Event::StartText(e) => StrEvent::StartText(self.to_str(e.into_inner())?),
+            Event::Start(e) => StrEvent::Start(e),
+            Event::End(e) => StrEvent::End(self.to_str(e.into_inner())?),
+            Event::Empty(e) => StrEvent::Empty(e),
+            Event::Text(e) => StrEvent::Text(self.to_str(e.into_inner())?),
+            Event::Comment(e) => StrEvent::Comment(self.to_str(e.into_inner())?),
+            Event::CData(e) => StrEvent::CData(self.to_str(e.into_inner())?),
+            Event::Decl(e) => StrEvent::Decl(e),
+            Event::PI(e) => StrEvent::PI(self.to_str(e.into_inner())?),
+            Event::DocType(e) => StrEvent::DocType(self.to_str(e.into_inner())?),
+            Event::Attribute(a) => {
+                let (key, value) = match a {
+                    Attr::DoubleQ(key, value)
+                    | Attr::SingleQ(key, value)
+                    | Attr::Unquoted(key, value) => (key, self.to_str(value)?),
+                    Attr::Empty(key) => (key, Cow::Borrowed("")),
+                };
+                StrEvent::Attribute(self.to_str(key)?, value)
+            }
+            Event::AttributesEnd => StrEvent::AttributesEnd,
+            Event::Eof => StrEvent::Eof,
+        })
+    }
+
+    /// Converts content of an event to a string. Borrowed content, that points
+    /// into the input, is sliced from the input string without validation
+    fn to_str(&self, bytes: Cow<'a, [u8]>) -> Result<Cow<'a, str>> {
+        match bytes {
+            Cow::Borrowed(bytes) => {
+                let start = (bytes.as_ptr() as usize).wrapping_sub(self.input.as_ptr() as usize);
+                let slice = start
+                    .checked_add(bytes.len())
+                    .and_then(|end| self.input.get(start..end));
+                match slice {
+                    Some(s) if s.as_ptr() == bytes.as_ptr() => Ok(Cow::Borrowed(s)),
+                    _ => Ok(Cow::Borrowed(from_utf8(bytes)?)),
+                }
+            }
+            Cow::Owned(bytes) => Ok(Cow::Owned(String::from_utf8(bytes)?)),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for StrReader<'a> {
+    #[inline]
+    fn from(input: &'a str) -> Self {
+        Self::new(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Error;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn events() {
+        let mut reader = StrReader::new(
+            "<?xml version='1.0'?><!DOCTYPE root><root a='1'><!--ком--><![CDATA[дан]]>\
+             текст&amp;<?pi?><empty/></root>",
+        );
+        let mut events = Vec::new();
+        loop {
+            match reader.read_event().unwrap() {
+                StrEvent::Eof => break,
+                StrEvent::Start(e) => events.push(format!("Start({:?})", e.name())),
+                StrEvent::Empty(e) => events.push(format!("Empty({:?})", e.name())),
+                StrEvent::Decl(_) => events.push("Decl".to_string()),
+                e => events.push(format!("{:?}", e)),
+            }
+        }
+        assert_eq!(
+            events,
+            vec![
+                "Decl",
+                r#"DocType("root")"#,
+                r#"Start(QName("root"))"#,
+                r#"Comment("ком")"#,
+                r#"CData("дан")"#,
+                r#"Text("текст&amp;")"#,
+                r#"PI("pi")"#,
+                r#"Empty(QName("empty"))"#,
+                r#"End("root")"#,
+            ]
+        );
+    }
+
+    /// Content, that is not borrowed from the input, should be validated
+    #[test]
+    fn mapped_names() {
+        let mut reader = StrReader::new("<a></a>");
+        reader.get_mut().map_names(|name| match name {
+            b"a" => Some(Cow::Owned(b"\xFF".to_vec())),
+            _ => None,
+        });
+
+        assert!(matches!(reader.read_event(), Ok(StrEvent::Start(_))));
+        assert!(matches!(reader.read_event(), Err(Error::NonDecodable(_))));
+
+        let mut reader = StrReader::new("<c></c>");
+        reader.get_mut().map_names(|name| match name {
+            b"c" => Some(Cow::Borrowed(b"mapped")),
+            _ => None,
+        });
+        assert!(matches!(reader.read_event(), Ok(StrEvent::Start(_))));
+        assert_eq!(reader.read_event().unwrap(), StrEvent::End("mapped".into()));
+    }
+
+    #[test]
+    fn attributes() {
+        let mut reader = StrReader::new("<tag a='значение'/>");
+        reader.get_mut().stream_attributes(true);
+
+        assert!(matches!(reader.read_event(), Ok(StrEvent::Start(_))));
+        assert_eq!(
+            reader.read_event().unwrap(),
+            StrEvent::Attribute("a".into(), "значение".into())
+        );
+        assert_eq!(reader.read_event().unwrap(), StrEvent::AttributesEnd);
+        assert_eq!(reader.read_event().unwrap(), StrEvent::End("tag".into()));
+        assert_eq!(reader.read_event().unwrap(), StrEvent::Eof);
+    }
+}
@@ -0,0 +1,200 @@
+//! Helpers for testing code that produces or consumes XML events.
+//!
+//! These functions parse a document and compare the produced events with
+//! the expected ones. On mismatch they panic with a message that lists both
+//! sequences side by side, with names and text decoded from UTF-8, which is
+//! easier to read than the `Debug` output of the events.
+
+use crate::errors::Error;
+use crate::events::Event;
+use crate::reader::Reader;
+use std::fmt::Write;
+use std::io::BufRead;
+
+/// Parses `xml` and asserts that the reader produces exactly the `expected`
+/// events, not counting the final [`Event::Eof`].
+///
+/// The events borrow from the input, as returned by [`Reader::read_event`].
+///
+/// # Panics
+///
+/// Panics if the events differ or if the reader returns an error.
+///
+/// ```
+/// use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+/// use quick_xml::test_util::assert_events;
+///
+/// assert_events(
+///     "<tag>text</tag>",
+///     &[
+///         Event::Start(BytesStart::borrowed_name(b"tag")),
+///         Event::Text(BytesText::from_escaped_str("text")),
+///         Event::End(BytesEnd::borrowed(b"tag")),
+///     ],
+/// );
+/// ```
+pub fn assert_events(xml: &str, expected: &[Event]) {
+    assert_reader_events(&mut Reader::from_str(xml), expected);
+}
+
+/// Asserts that the `reader` produces exactly the `expected` events, not
+/// counting the final [`Event::Eof`].
+///
+/// This function reads from the [`Reader`] as is, so it can be configured
+/// before the call, for example, to [trim text].
+///
+/// # Panics
+///
+/// Panics if the events differ or if the reader returns an error.
+///
+/// [trim text]: Reader::trim_text
+pub fn assert_reader_events(reader: &mut Reader<&[u8]>, expected: &[Event]) {
+    let mut events = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(e) => events.push(e),
+            Err(e) => fail(&events, expected, reader.buffer_position(), e),
+        }
+    }
+    compare(&events, expected);
+}
+
+/// Asserts that the `reader` produces exactly the `expected` events, not
+/// counting the final [`Event::Eof`].
+///
+/// This is a variant of [`assert_reader_events`] that reads from any
+/// [`BufRead`] source with [`Reader::read_event_into`].
+///
+/// # Panics
+///
+/// Panics if the events differ or if the reader returns an error.
+///
+/// ```
+/// use quick_xml::events::{BytesStart, Event};
+/// use quick_xml::test_util::assert_buffered_events;
+/// use quick_xml::Reader;
+/// use std::io::BufReader;
+///
+/// let mut reader = Reader::from_reader(BufReader::new(b"<tag/>".as_ref()));
+/// assert_buffered_events(&mut reader, &[Event::Empty(BytesStart::borrowed_name(b"tag"))]);
+/// ```
+pub fn assert_buffered_events<R: BufRead>(reader: &mut Reader<R>, expected: &[Event]) {
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(e) => events.push(e.into_owned()),
+            Err(e) => fail(&events, expected, reader.buffer_position(), e),
+        }
+        buf.clear();
+    }
+    compare(&events, expected);
+}
+
+fn compare(actual: &[Event], expected: &[Event]) {
+    if actual != expected {
+        panic!(
+            "events are not equal (- expected / + actual):\n{}",
+            diff(actual, expected)
+        );
+    }
+}
+
+fn fail(actual: &[Event], expected: &[Event], position: usize, error: Error) -> ! {
+    let position = error.position().unwrap_or(position);
+    panic!(
+        "error at position {}: {}\nevents read so far (- expected / + actual):\n{}",
+        position,
+        error,
+        diff(actual, expected)
+    );
+}
+
+/// Renders both sequences line by line, marking lines that differ
+fn diff(actual: &[Event], expected: &[Event]) -> String {
+    let mut result = String::new();
+    for i in 0..actual.len().max(expected.len()) {
+        let actual = actual.get(i).map(describe);
+        let expected = expected.get(i).map(describe);
+        if actual == expected {
+            writeln!(result, "  {}", actual.unwrap_or_default()).unwrap();
+        } else {
+            if let Some(expected) = expected {
+                writeln!(result, "- {}", expected).unwrap();
+            }
+            if let Some(actual) = actual {
+                writeln!(result, "+ {}", actual).unwrap();
+            }
+        }
+    }
+    result
+}
+
+/// Describes an event with its content decoded from UTF-8
+fn describe(event: &Event) -> String {
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    match event {
+        Event::StartText(e) => format!("StartText({:?})", text(e)),
+        Event::Start(e) => format!("Start(<{}>)", text(e)),
+        Event::Empty(e) => format!("Empty(<{}/>)", text(e)),
+        Event::End(e) => format!("End(</{}>)", text(e)),
+        Event::Text(e) => format!("Text({:?})", text(e)),
+        Event::Comment(e) => format!("Comment({:?})", text(e)),
+        Event::CData(e) => format!("CData({:?})", text(e)),
+        Event::Decl(e) => format!("Decl(<?{}?>)", text(e)),
+        Event::PI(e) => format!("PI(<?{}?>)", text(e)),
+        Event::DocType(e) => format!("DocType({:?})", text(e)),
+        Event::Attribute(a) => format!("Attribute({:?})", a),
+        Event::AttributesEnd => "AttributesEnd".to_string(),
+        Event::Eof => "Eof".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{BytesEnd, BytesStart, BytesText};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn equal() {
+        let expected = [
+            Event::Start(BytesStart::borrowed(b"tag a='1'", 3)),
+            Event::Text(BytesText::from_escaped_str("text")),
+            Event::End(BytesEnd::borrowed(b"tag")),
+        ];
+        assert_events("<tag a='1'>text</tag>", &expected);
+        assert_buffered_events(
+            &mut Reader::from_reader(b"<tag a='1'>text</tag>".as_ref()),
+            &expected,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "error at position 7: Expecting </tag> found </other>")]
+    fn error() {
+        assert_events("<tag></other>", &[]);
+    }
+
+    #[test]
+    fn diff_output() {
+        let actual = [
+            Event::Start(BytesStart::borrowed_name(b"tag")),
+            Event::Text(BytesText::from_escaped_str("text")),
+        ];
+        let expected = [
+            Event::Start(BytesStart::borrowed_name(b"tag")),
+            Event::Text(BytesText::from_escaped_str("other")),
+            Event::End(BytesEnd::borrowed(b"tag")),
+        ];
+        assert_eq!(
+            diff(&actual, &expected),
+            "  Start(<tag>)\n\
+             - Text(\"other\")\n\
+             + Text(\"text\")\n\
+             - End(</tag>)\n"
+        );
+    }
+}
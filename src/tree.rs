@@ -0,0 +1,350 @@
+//! A minimal owned tree of XML nodes, built from the events of a [`Reader`]
+//!
+//! This module provides a simple document object model for cases when an
+//! element is small enough to be kept in memory and it is more convenient to
+//! work with it as a whole than with a stream of events. All names, values
+//! and texts are decoded and unescaped, so the tree does not depend on the
+//! encoding of the input.
+
+use crate::errors::{Error, Result};
+use crate::events::attributes::Attr;
+use crate::events::{BytesCData, BytesEnd, BytesStart, BytesText, Event};
+use crate::reader::Reader;
+use crate::writer::Writer;
+use std::io::{BufRead, Write};
+
+/// A node of an XML tree, returned by [`Reader::read_node`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XmlNode {
+    /// An element with its attributes and child nodes.
+    Element {
+        /// Qualified name of the element, including the prefix, if any
+        name: String,
+        /// Qualified names and unescaped values of attributes in the order
+        /// in which they appear in the document
+        attrs: Vec<(String, String)>,
+        /// Child nodes in document order
+        children: Vec<XmlNode>,
+    },
+    /// Unescaped character data.
+    Text(String),
+    /// Content of a CDATA section `<![CDATA[...]]>`.
+    CData(String),
+    /// Content of a comment `<!-- ... -->`.
+    Comment(String),
+    /// Content of a processing instruction `<?...?>`.
+    PI(String),
+}
+
+impl XmlNode {
+    /// Writes this node with all its descendants to the `writer`.
+    ///
+    /// Text and attribute values are escaped, so the written document can be
+    /// read back into the same tree. Elements without children are written
+    /// as empty elements `<tag/>`.
+    pub fn write_to<W: Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        match self {
+            XmlNode::Element {
+                name,
+                attrs,
+                children,
+            } => {
+                let mut start = BytesStart::borrowed_name(name.as_bytes());
+                for (key, value) in attrs {
+                    start.push_attribute_escaped((key.as_bytes(), value.as_bytes()));
+                }
+                if children.is_empty() {
+                    return writer.write_event(Event::Empty(start));
+                }
+                writer.write_event(Event::Start(start))?;
+                for child in children {
+                    child.write_to(writer)?;
+                }
+                writer.write_event(Event::End(BytesEnd::borrowed(name.as_bytes())))
+            }
+            XmlNode::Text(text) => writer.write_event(Event::Text(BytesText::from_plain_str(text))),
+            XmlNode::CData(text) => {
+                writer.write_event(Event::CData(BytesCData::new(text.as_bytes())))
+            }
+            XmlNode::Comment(text) => {
+                writer.write_event(Event::Comment(BytesText::from_escaped_str(text.as_str())))
+            }
+            XmlNode::PI(text) => {
+                writer.write_event(Event::PI(BytesText::from_escaped_str(text.as_str())))
+            }
+        }
+    }
+}
+
+/// An element which [`End`](Event::End) event was not read yet
+struct Open {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+impl Open {
+    fn into_node(self) -> XmlNode {
+        XmlNode::Element {
+            name: self.name,
+            attrs: self.attrs,
+            children: self.children,
+        }
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Reads the next node with all its descendants.
+    ///
+    /// If the next event is a [`Start`] event, all events up to the
+    /// corresponding [`End`] event are consumed and collected into an
+    /// [`XmlNode::Element`]. Other events are returned as the corresponding
+    /// single nodes. XML declaration, DOCTYPE and text before the first markup
+    /// are not a part of the element tree and are skipped.
+    ///
+    /// All settings of the reader are respected, so, for example, to skip
+    /// whitespaces between elements, enable [`trim_text`].
+    ///
+    /// Returns [`Error::UnexpectedEof`] if the input ends before the node is
+    /// complete or before any node was found, and [`Error::EndEventMismatch`]
+    /// if the next event is an [`End`] event, that is, there are no more nodes
+    /// in the current element.
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// use quick_xml::tree::XmlNode;
+    /// use quick_xml::Reader;
+    ///
+    /// let mut reader = Reader::from_str(r#"<root a="1 &amp; 2">text<child/></root>"#);
+    /// let mut buf = Vec::new();
+    ///
+    /// assert_eq!(
+    ///     reader.read_node(&mut buf).unwrap(),
+    ///     XmlNode::Element {
+    ///         name: "root".into(),
+    ///         attrs: vec![("a".into(), "1 & 2".into())],
+    ///         children: vec![
+    ///             XmlNode::Text("text".into()),
+    ///             XmlNode::Element {
+    ///                 name: "child".into(),
+    ///                 attrs: vec![],
+    ///                 children: vec![],
+    ///             },
+    ///         ],
+    ///     }
+    /// );
+    /// ```
+    ///
+    /// [`Start`]: Event::Start
+    /// [`End`]: Event::End
+    /// [`trim_text`]: Self::trim_text
+    pub fn read_node(&mut self, buf: &mut Vec<u8>) -> Result<XmlNode> {
+        let mut stack: Vec<Open> = Vec::new();
+        loop {
+            buf.clear();
+            let node = match self.read_event_into(buf)? {
+                Event::Start(e) => {
+                    let open = self.open(&e)?;
+                    stack.push(self.read_streamed_attributes(buf, open)?);
+                    continue;
+                }
+                Event::Empty(e) => {
+                    let open = self.open(&e)?;
+                    self.read_streamed_attributes(buf, open)?.into_node()
+                }
+                Event::End(e) => match stack.pop() {
+                    Some(open) => open.into_node(),
+                    None => {
+                        return Err(Error::EndEventMismatch {
+                            expected: String::new(),
+                            found: self.decoder().decode(e.name().as_ref())?.into_owned(),
+                            position: self.buffer_position(),
+                        })
+                    }
+                },
+                Event::Text(e) => XmlNode::Text(e.unescape_and_decode(self)?),
+                Event::CData(e) => XmlNode::CData(self.decoder().decode(&e)?.into_owned()),
+                Event::Comment(e) => XmlNode::Comment(self.decoder().decode(&e)?.into_owned()),
+                Event::PI(e) => XmlNode::PI(self.decoder().decode(&e)?.into_owned()),
+                Event::StartText(_)
+                | Event::Decl(_)
+                | Event::DocType(_)
+                | Event::Attribute(_)
+                | Event::AttributesEnd => continue,
+                Event::Eof => {
+                    let what = match stack.last() {
+                        Some(open) => format!("</{}>", open.name),
+                        None => "Node".to_string(),
+                    };
+                    return Err(Error::UnexpectedEof(what, self.buffer_position()));
+                }
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => return Ok(node),
+            }
+        }
+    }
+
+    /// Creates an element without children from a `Start` or `Empty` event,
+    /// decoding and unescaping all its attributes
+    fn open(&self, e: &BytesStart) -> Result<Open> {
+        let decoder = self.decoder();
+        let attrs = e
+            .attributes_decoded(self, true)
+            .map(|attr| {
+                let (key, value) = attr?;
+                Ok((
+                    decoder.decode(key.as_ref())?.into_owned(),
+                    value.into_owned(),
+                ))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Open {
+            name: decoder.decode(e.name().as_ref())?.into_owned(),
+            attrs,
+            children: Vec::new(),
+        })
+    }
+
+    /// Reads [`Attribute`](Event::Attribute) events that follow the element
+    /// start when [`Self::stream_attributes`] is enabled
+    fn read_streamed_attributes(&mut self, buf: &mut Vec<u8>, mut open: Open) -> Result<Open> {
+        if !self.config().stream_attributes {
+            return Ok(open);
+        }
+        loop {
+            buf.clear();
+            match self.read_event_into(buf)? {
+                Event::Attribute(attr) => {
+                    let (key, value) = match &attr {
+                        Attr::DoubleQ(key, value)
+                        | Attr::SingleQ(key, value)
+                        | Attr::Unquoted(key, value) => {
+                            (key, self.decode_and_unescape(value)?.into_owned())
+                        }
+                        Attr::Empty(key) => (key, String::new()),
+                    };
+                    let key = self.decoder().decode(key)?.into_owned();
+                    open.attrs.push((key, value));
+                }
+                _ => return Ok(open),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn element(name: &str, attrs: &[(&str, &str)], children: Vec<XmlNode>) -> XmlNode {
+        XmlNode::Element {
+            name: name.into(),
+            attrs: attrs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            children,
+        }
+    }
+
+    /// Reads all nodes until the end of input
+    fn read_all<R: BufRead>(reader: &mut Reader<R>) -> Vec<XmlNode> {
+        let mut buf = Vec::new();
+        let mut nodes = Vec::new();
+        loop {
+            match reader.read_node(&mut buf) {
+                Ok(node) => nodes.push(node),
+                Err(Error::UnexpectedEof(what, _)) if what == "Node" => break,
+                Err(e) => panic!("Unexpected error: {:?}", e),
+            }
+        }
+        nodes
+    }
+
+    #[test]
+    fn nodes() {
+        let mut reader = Reader::from_str(
+            "<?xml version='1.0'?><!DOCTYPE root><!--c--><?pi?>\
+             <root a='&lt;'>&amp;<![CDATA[<x>]]><e b=''/></root>",
+        );
+        assert_eq!(
+            read_all(&mut reader),
+            vec![
+                XmlNode::Comment("c".into()),
+                XmlNode::PI("pi".into()),
+                element(
+                    "root",
+                    &[("a", "<")],
+                    vec![
+                        XmlNode::Text("&".into()),
+                        XmlNode::CData("<x>".into()),
+                        element("e", &[("b", "")], vec![]),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    /// Attributes should be collected when they are emitted as separate events
+    #[test]
+    fn streamed_attributes() {
+        let mut reader = Reader::from_str("<a x='1'><b y='2' z='&amp;'/></a><c w='3'/>");
+        reader.stream_attributes(true);
+        assert_eq!(
+            read_all(&mut reader),
+            vec![
+                element(
+                    "a",
+                    &[("x", "1")],
+                    vec![element("b", &[("y", "2"), ("z", "&")], vec![])]
+                ),
+                element("c", &[("w", "3")], vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut buf = Vec::new();
+
+        let mut reader = Reader::from_str("<a><b></b>");
+        match reader.read_node(&mut buf) {
+            Err(Error::UnexpectedEof(what, _)) => assert_eq!(what, "</a>"),
+            x => panic!("Expected `UnexpectedEof`, got {:?}", x),
+        }
+
+        let mut reader = Reader::from_str("</a>");
+        reader.check_end_names(false);
+        match reader.read_node(&mut buf) {
+            Err(Error::EndEventMismatch { found, .. }) => assert_eq!(found, "a"),
+            x => panic!("Expected `EndEventMismatch`, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let xml = include_str!("../tests/documents/players.xml");
+        let nodes = read_all(&mut Reader::from_str(xml));
+        assert!(matches!(nodes[0], XmlNode::Comment(_)));
+        match &nodes[1] {
+            XmlNode::Element { name, children, .. } => {
+                assert_eq!(name, "game");
+                assert!(!children.is_empty());
+            }
+            node => panic!("Expected `game` element, got {:?}", node),
+        }
+
+        let mut writer = Writer::new(Vec::new());
+        for node in &nodes {
+            node.write_to(&mut writer).unwrap();
+        }
+        let written = writer.into_inner();
+        assert_eq!(
+            read_all(&mut Reader::from_reader(written.as_slice())),
+            nodes
+        );
+    }
+}
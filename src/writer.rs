@@ -88,6 +88,9 @@ impl<W: Write> Writer<W> {
     }
 
     /// Writes the given event to the underlying writer.
+    ///
+    /// [`Event::Attribute`] and [`Event::AttributesEnd`] cannot be written and
+    /// [`Error::UnexpectedToken`] is returned for them.
     pub fn write_event<'a, E: AsRef<Event<'a>>>(&mut self, event: E) -> Result<()> {
         let mut next_should_line_break = true;
         let result = match *event.as_ref() {
@@ -120,6 +123,11 @@ impl<W: Write> Writer<W> {
             Event::Decl(ref e) => self.write_wrapped(b"<?", e, b"?>"),
             Event::PI(ref e) => self.write_wrapped(b"<?", e, b"?>"),
             Event::DocType(ref e) => self.write_wrapped(b"<!DOCTYPE ", e, b">"),
+            // Attributes cannot be written after the element start, because
+            // it is already closed
+            Event::Attribute(_) | Event::AttributesEnd => {
+                Err(Error::UnexpectedToken("Attribute".to_string()))
+            }
             Event::Eof => Ok(()),
         };
         if let Some(i) = self.indent.as_mut() {
@@ -300,6 +308,115 @@ impl<'a, W: Write> ElementWriter<'a, W> {
     }
 }
 
+/// An iterator adapter that inserts indentation into a stream of events.
+///
+/// `EventFormatter` expects a stream of events without formatting whitespaces
+/// (for example, produced by a [`Reader`] with [`trim_text(true)`]) and yields
+/// the same events interleaved with synthetic [`Event::Text`] events, containing
+/// a line break and indentation according to the current nesting depth. The
+/// output can be written by a plain [`Writer`], which makes it a building block
+/// for XML pretty-printers.
+///
+/// The same heuristic as in [`Writer::new_with_indent`] is used: no indentation
+/// is inserted after [`Event::Text`] and [`Event::CData`] events.
+///
+/// # Example
+///
+/// ```rust
+/// # use pretty_assertions::assert_eq;
+/// use quick_xml::{EventFormatter, Reader, Writer};
+/// use quick_xml::events::Event;
+///
+/// let mut reader = Reader::from_str("<root>\n<a>text</a>  <b/></root>");
+/// reader.trim_text(true);
+///
+/// let mut events = Vec::new();
+/// loop {
+///     match reader.read_event().unwrap() {
+///         Event::Eof => break,
+///         e => events.push(e),
+///     }
+/// }
+///
+/// let mut writer = Writer::new(Vec::new());
+/// for event in EventFormatter::new(events.into_iter(), b' ', 2) {
+///     writer.write_event(event).unwrap();
+/// }
+/// assert_eq!(
+///     std::str::from_utf8(&writer.into_inner()).unwrap(),
+///     "<root>\n  <a>text</a>\n  <b/>\n</root>"
+/// );
+/// ```
+///
+/// [`Reader`]: crate::Reader
+/// [`trim_text(true)`]: crate::Reader::trim_text
+#[derive(Clone)]
+pub struct EventFormatter<'a, I> {
+    events: I,
+    indent: Indentation,
+    /// Event that should be returned after the already returned indentation
+    pending: Option<Event<'a>>,
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> EventFormatter<'a, I> {
+    /// Creates a formatter over `events`, which indents each level of nesting
+    /// with `indent_size` characters `indent_char`
+    pub fn new(events: I, indent_char: u8, indent_size: usize) -> Self {
+        EventFormatter {
+            events,
+            indent: Indentation::new(indent_char, indent_size),
+            pending: None,
+        }
+    }
+
+    /// Consumes this `EventFormatter`, returning the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.events
+    }
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFormatter<'a, I> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        let event = self.events.next()?;
+
+        let (line_break, next_should_line_break) = match event {
+            Event::Eof | Event::Attribute(_) | Event::AttributesEnd => return Some(event),
+            Event::StartText(_) => (false, true),
+            Event::Text(_) | Event::CData(_) => (false, false),
+            Event::End(_) => {
+                self.indent.shrink();
+                (true, true)
+            }
+            _ => (true, true),
+        };
+        let indent = if line_break && self.indent.should_line_break {
+            let mut indent = Vec::with_capacity(self.indent.indents_len + 1);
+            indent.push(b'\n');
+            indent.extend_from_slice(&self.indent.indents[..self.indent.indents_len]);
+            Some(Event::Text(BytesText::from_escaped(indent)))
+        } else {
+            None
+        };
+        if let Event::Start(_) = event {
+            self.indent.grow();
+        }
+        self.indent.should_line_break = next_should_line_break;
+
+        match indent {
+            Some(indent) => {
+                self.pending = Some(event);
+                Some(indent)
+            }
+            None => Some(event),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct Indentation {
     should_line_break: bool,
@@ -578,6 +695,50 @@ mod indentation {
     <inner>
         <empty/>
     </inner>
+</outer>"#
+        );
+    }
+
+    /// `EventFormatter` should produce the same output as the indenting writer
+    #[test]
+    fn event_formatter() {
+        let events = vec![
+            Event::Start(BytesStart::borrowed_name(b"outer")),
+            Event::Comment(BytesText::from_escaped_str("comment")),
+            Event::Start(BytesStart::borrowed_name(b"paired")),
+            Event::Text(BytesText::from_plain_str("text")),
+            Event::End(BytesEnd::borrowed(b"paired")),
+            Event::Start(BytesStart::borrowed_name(b"inner")),
+            Event::Empty(BytesStart::borrowed_name(b"empty")),
+            Event::End(BytesEnd::borrowed(b"inner")),
+            Event::End(BytesEnd::borrowed(b"outer")),
+            Event::Eof,
+        ];
+
+        let mut expected = Vec::new();
+        let mut writer = Writer::new_with_indent(&mut expected, b' ', 4);
+        for event in events.iter() {
+            writer.write_event(event).expect("write event failed");
+        }
+
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(&mut buffer);
+        for event in EventFormatter::new(events.into_iter(), b' ', 4) {
+            writer.write_event(event).expect("write event failed");
+        }
+
+        assert_eq!(
+            std::str::from_utf8(&buffer).unwrap(),
+            std::str::from_utf8(&expected).unwrap()
+        );
+        assert_eq!(
+            std::str::from_utf8(&buffer).unwrap(),
+            r#"<outer>
+    <!--comment-->
+    <paired>text</paired>
+    <inner>
+        <empty/>
+    </inner>
 </outer>"#
         );
     }
@@ -375,3 +375,446 @@ fn reserved_name() {
         ),
     }
 }
+
+/// Checkpoint, created after an empty element, should not contain bindings
+/// of that element, because they go out of scope on the next read
+#[test]
+fn checkpoint_after_empty() {
+    let mut r = Reader::from_str("<a xmlns='a'><b xmlns='b'/><c xmlns='c'><d/></c></a>");
+
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+
+    // <a>
+    r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap();
+    // <b/>
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Empty(_))) => assert_eq!(ns, Bound(Namespace(b"b"))),
+        e => panic!("expecting empty element in 'b' namespace, got {:?}", e),
+    }
+
+    let checkpoint = r.namespace_checkpoint(&mut ns_buf);
+    // <c>
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Start(_))) => assert_eq!(ns, Bound(Namespace(b"c"))),
+        e => panic!("expecting start element in 'c' namespace, got {:?}", e),
+    }
+    // <d/>
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Empty(_))) => assert_eq!(ns, Bound(Namespace(b"c"))),
+        e => panic!("expecting empty element in 'c' namespace, got {:?}", e),
+    }
+    r.restore_namespace_checkpoint(checkpoint, &mut ns_buf);
+    assert_eq!(ns_buf, b"a");
+    assert_eq!(
+        r.event_namespace(QName(b"x"), &ns_buf).0,
+        Bound(Namespace(b"a"))
+    );
+
+    // Skipped by the nested routine
+    // </c>
+    r.read_event_into(&mut buf).unwrap();
+
+    // </a>
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, End(_))) => assert_eq!(ns, Bound(Namespace(b"a"))),
+        e => panic!("expecting end element in 'a' namespace, got {:?}", e),
+    }
+}
+
+/// Only bindings of the current element should be returned, even if they
+/// rebind prefixes of the parent elements
+#[test]
+fn locally_declared_namespaces() {
+    /// Reads the next event and returns bindings of the current element
+    fn next(r: &mut Reader<&[u8]>, ns_buf: &mut Vec<u8>) -> Vec<(Option<String>, String)> {
+        let mut buf = Vec::new();
+        r.read_namespaced_event(&mut buf, ns_buf).unwrap();
+        r.locally_declared_namespaces(ns_buf)
+            .map(|(prefix, ns)| {
+                (
+                    prefix.map(|p| String::from_utf8(p.to_vec()).unwrap()),
+                    String::from_utf8(ns.to_vec()).unwrap(),
+                )
+            })
+            .collect()
+    }
+    let p = |prefix: &str, ns: &str| (Some(prefix.to_string()), ns.to_string());
+    let default = |ns: &str| (None, ns.to_string());
+
+    let mut r = Reader::from_str(
+        "<a xmlns:p='urn:a' xmlns='urn:x'><b xmlns:p='urn:b' xmlns:q='urn:q'>\
+         <c/><d xmlns:p=''/></b>text</a>",
+    );
+    let mut ns_buf = Vec::new();
+
+    // <a>
+    assert_eq!(
+        next(&mut r, &mut ns_buf),
+        vec![p("p", "urn:a"), default("urn:x")]
+    );
+    // <b>
+    assert_eq!(
+        next(&mut r, &mut ns_buf),
+        vec![p("p", "urn:b"), p("q", "urn:q")]
+    );
+    assert_eq!(
+        r.event_namespace(QName(b"p:x"), &ns_buf).0,
+        Bound(Namespace(b"urn:b"))
+    );
+    // <c/>
+    assert_eq!(next(&mut r, &mut ns_buf), vec![]);
+    // <d/>
+    assert_eq!(next(&mut r, &mut ns_buf), vec![p("p", "")]);
+    // </b>
+    assert_eq!(
+        next(&mut r, &mut ns_buf),
+        vec![p("p", "urn:b"), p("q", "urn:q")]
+    );
+    // text
+    assert_eq!(
+        next(&mut r, &mut ns_buf),
+        vec![p("p", "urn:a"), default("urn:x")]
+    );
+    assert_eq!(
+        r.event_namespace(QName(b"p:x"), &ns_buf).0,
+        Bound(Namespace(b"urn:a"))
+    );
+}
+
+/// Attributes with different prefixes, bound to the same namespace, are duplicates
+#[test]
+fn duplicate_attributes() {
+    let mut r = Reader::from_str(
+        "<a xmlns:p='urn:x' xmlns:q='urn:x' x='1' p:x='2'><b p:x='1' q:x='2'/></a>",
+    );
+    r.check_duplicate_attributes(true);
+
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((Unbound, Start(e))) => assert_eq!(e.name(), QName(b"a")),
+        e => panic!("expecting Start, found {:?}", e),
+    }
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Err(quick_xml::Error::DuplicateAttribute { name, position }) => {
+            assert_eq!(name, "q:x");
+            assert_eq!(position, 60);
+            assert_eq!(r.buffer_position(), 60);
+        }
+        e => panic!("expecting DuplicateAttribute, found {:?}", e),
+    }
+    assert!(matches!(
+        r.read_namespaced_event(&mut buf, &mut ns_buf),
+        Ok((_, Eof))
+    ));
+}
+
+/// Duplicates are detected in expanded empty elements
+#[test]
+fn duplicate_attributes_expanded() {
+    let mut r = Reader::from_str("<a xmlns:p='urn:x' xmlns:q='urn:x' p:x='1' q:x='2'/>");
+    r.check_duplicate_attributes(true)
+        .expand_empty_elements(true);
+
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Err(quick_xml::Error::DuplicateAttribute { name, position }) => {
+            assert_eq!(name, "q:x");
+            assert_eq!(position, 43);
+        }
+        e => panic!("expecting DuplicateAttribute, found {:?}", e),
+    }
+}
+
+/// Unprefixed attributes are resolved into the default namespace when requested
+#[test]
+fn attributes_inherit_default_ns() {
+    let src = "<a xmlns='urn:x' xmlns:p='urn:x' x='1' p:x='2'/>";
+
+    let mut r = Reader::from_str(src);
+    r.check_duplicate_attributes(true);
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((Bound(_), Empty(_))) => {
+            let (ns, _) = r.attribute_namespace(QName(b"x"), &ns_buf);
+            assert_eq!(ns, Unbound);
+        }
+        e => panic!("expecting Empty, found {:?}", e),
+    }
+
+    let mut r = Reader::from_str(src);
+    r.check_duplicate_attributes(true)
+        .attributes_inherit_default_ns(true);
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Err(quick_xml::Error::DuplicateAttribute { name, position }) => {
+            assert_eq!(name, "p:x");
+            assert_eq!(position, 39);
+        }
+        e => panic!("expecting DuplicateAttribute, found {:?}", e),
+    }
+}
+
+/// Namespace scopes are consistent after skipping a subtree
+#[test]
+fn skip_subtree() {
+    let mut r = Reader::from_str(
+        "<root xmlns='urn:d'><skip xmlns='urn:s'><skip><a/></skip></skip><b/></root><c/>",
+    );
+
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Start(e))) => {
+            assert_eq!(ns, Bound(Namespace(b"urn:d")));
+            assert_eq!(e.name(), QName(b"root"));
+        }
+        e => panic!("expecting Start, found {:?}", e),
+    }
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Start(e))) => {
+            assert_eq!(ns, Bound(Namespace(b"urn:s")));
+            assert_eq!(e.name(), QName(b"skip"));
+        }
+        e => panic!("expecting Start, found {:?}", e),
+    }
+    r.skip_subtree_ns(QName(b"skip"), &mut buf, &mut ns_buf)
+        .unwrap();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Empty(e))) => {
+            assert_eq!(ns, Bound(Namespace(b"urn:d")));
+            assert_eq!(e.name(), QName(b"b"));
+        }
+        e => panic!("expecting Empty, found {:?}", e),
+    }
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, End(e))) => {
+            assert_eq!(ns, Bound(Namespace(b"urn:d")));
+            assert_eq!(e.name(), QName(b"root"));
+        }
+        e => panic!("expecting End, found {:?}", e),
+    }
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Empty(e))) => {
+            assert_eq!(ns, Unbound);
+            assert_eq!(e.name(), QName(b"c"));
+        }
+        e => panic!("expecting Empty, found {:?}", e),
+    }
+}
+
+#[test]
+fn attributes_sorted() {
+    let mut r = Reader::from_str(
+        "<a xmlns:z='urn:a' xmlns:b='urn:b' b:x='1' z:y='2' z:x='3' x='4' xmlns='urn:d'/>",
+    );
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((_, Empty(e))) => {
+            let sorted = e.attributes_sorted_ns(&r, &ns_buf).unwrap();
+            let keys: Vec<_> = sorted.iter().map(|a| a.key).collect();
+            assert_eq!(
+                keys,
+                vec![
+                    QName(b"xmlns"),
+                    QName(b"xmlns:b"),
+                    QName(b"xmlns:z"),
+                    QName(b"x"),
+                    QName(b"z:x"),
+                    QName(b"z:y"),
+                    QName(b"b:x"),
+                ]
+            );
+        }
+        e => panic!("expecting Empty, found {:?}", e),
+    }
+
+    // Unknown prefix
+    let mut r = Reader::from_str("<a p:x='1'/>");
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((_, Empty(e))) => match e.attributes_sorted_ns(&r, &ns_buf) {
+            Err(quick_xml::Error::UnknownPrefix(p)) => assert_eq!(p, b"p"),
+            x => panic!("expecting UnknownPrefix, found {:?}", x),
+        },
+        e => panic!("expecting Empty, found {:?}", e),
+    }
+}
+
+#[test]
+fn owned() {
+    let mut r = Reader::from_str("<a xmlns='urn:a'><b:c xmlns:b='urn:b'/><d/></a><e/>");
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        match r.read_namespaced_event_owned(&mut buf).unwrap() {
+            (_, Eof) => break,
+            (ns, e) => events.push((ns, e.name().map(|n| n.as_ref().to_vec()))),
+        }
+        buf.clear();
+    }
+    let name = |n: &[u8]| Some(n.to_vec());
+    assert_eq!(
+        events,
+        vec![
+            (name(b"urn:a"), name(b"a")),
+            (name(b"urn:b"), name(b"b:c")),
+            (name(b"urn:a"), name(b"d")),
+            (name(b"urn:a"), name(b"a")),
+            (None, name(b"e")),
+        ]
+    );
+
+    // Unknown prefix
+    let mut r = Reader::from_str("<p:a/>");
+    match r.read_namespaced_event_owned(&mut buf) {
+        Err(quick_xml::Error::UnknownPrefix(p)) => assert_eq!(p, b"p"),
+        x => panic!("expecting UnknownPrefix, found {:?}", x),
+    }
+}
+
+/// Events other than `Start`, `Empty` and `End` do not have names, so they
+/// are never bound, even if a default namespace is in scope
+#[test]
+fn non_element_events_unbound() {
+    let mut r = Reader::from_str(
+        "<?xml version='1.0'?><!DOCTYPE root>\
+         <root xmlns='urn:d' xmlns:p='urn:p'><?p:pi?><!--c--><![CDATA[d]]>text</root>",
+    );
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+
+    let mut next = || match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, e)) => (ns.is_bound(), format!("{:?}", e)),
+        e => panic!("Unexpected result {:?}", e),
+    };
+    assert!(matches!(next(), (false, e) if e.starts_with("Decl")));
+    assert!(matches!(next(), (false, e) if e.starts_with("DocType")));
+    assert!(matches!(next(), (true, e) if e.starts_with("Start")));
+    assert!(matches!(next(), (false, e) if e.starts_with("PI")));
+    assert!(matches!(next(), (false, e) if e.starts_with("Comment")));
+    assert!(matches!(next(), (false, e) if e.starts_with("CData")));
+    assert!(matches!(next(), (false, e) if e.starts_with("Text")));
+    assert!(matches!(next(), (true, e) if e.starts_with("End")));
+    assert!(matches!(next(), (false, e) if e == "Eof"));
+}
+
+/// The scope of an empty element is kept until the next call, so its
+/// attributes can be resolved, and is popped by the next call, even if it
+/// returns `Eof`
+#[test]
+fn empty_pending_pop() {
+    for (xml, next) in [
+        ("<root><e xmlns:p='urn:p' p:a='1'/></root>", "End"),
+        ("<e xmlns:p='urn:p' p:a='1'/>", "Eof"),
+    ] {
+        let mut r = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        let mut ns_buf = Vec::new();
+
+        loop {
+            match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+                Ok((_, Empty(_))) => break,
+                Ok(_) => {}
+                e => panic!("Unexpected result {:?}", e),
+            }
+        }
+        assert_eq!(
+            r.attribute_namespace(QName(b"p:a"), &ns_buf).0,
+            Bound(Namespace(b"urn:p")),
+            "{}",
+            xml
+        );
+
+        match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+            Ok((Unbound, e)) => assert!(format!("{:?}", e).starts_with(next), "{}", xml),
+            e => panic!("Unexpected result {:?}", e),
+        }
+        assert_eq!(
+            r.attribute_namespace(QName(b"p:a"), &ns_buf).0,
+            Unknown(b"p".to_vec()),
+            "{}",
+            xml
+        );
+    }
+}
+
+/// Position of events with undeclared prefixes is available for diagnostics
+#[test]
+fn resolve_error_position() {
+    let mut r = Reader::from_str("<root xmlns:p='urn:p'><p:a/> <q:b>text</q:b></root>");
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+
+    let mut positions = Vec::new();
+    loop {
+        match r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap() {
+            (_, Eof) => break,
+            (Unknown(prefix), _) => {
+                assert_eq!(prefix, b"q");
+                positions.push(r.resolve_error_position());
+            }
+            _ => assert_eq!(r.resolve_error_position(), None),
+        }
+    }
+    // Both start and end tags of `q:b` are reported
+    assert_eq!(positions, vec![Some(29), Some(38)]);
+
+    let mut r = Reader::from_str("<root><q:b/></root>");
+    let mut buf = Vec::new();
+    r.read_namespaced_event_owned(&mut buf).unwrap();
+    match r.read_namespaced_event_owned(&mut buf) {
+        Err(quick_xml::Error::UnknownPrefix(prefix)) => assert_eq!(prefix, b"q"),
+        x => panic!("Expected `UnknownPrefix`, got {:?}", x),
+    }
+    assert_eq!(r.resolve_error_position(), Some(6));
+}
+
+/// Namespaces declared manually are resolved as declared by elements
+#[test]
+fn declare_namespace() {
+    let mut r = Reader::from_str("<p:a xmlns:q='urn:q'><b/></p:a><p:c/>");
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    r.declare_namespace(Some(b"p"), b"urn:p", &mut ns_buf);
+    r.declare_namespace(None, b"urn:default", &mut ns_buf);
+
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Start(_))) => assert_eq!(ns, Bound(Namespace(b"urn:p"))),
+        e => panic!("expecting outer start element, got {:?}", e),
+    }
+    // Declaration in the scope of the element
+    r.declare_namespace(Some(b"r"), b"urn:r", &mut ns_buf);
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Empty(_))) => assert_eq!(ns, Bound(Namespace(b"urn:default"))),
+        e => panic!("expecting inner empty element, got {:?}", e),
+    }
+    assert_eq!(
+        r.event_namespace(QName(b"r:x"), &ns_buf).0,
+        Bound(Namespace(b"urn:r"))
+    );
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, End(_))) => assert_eq!(ns, Bound(Namespace(b"urn:p"))),
+        e => panic!("expecting outer end element, got {:?}", e),
+    }
+
+    // The scope of the element is ended before the new scope is started
+    r.push_namespace_scope(&mut ns_buf);
+    r.declare_namespace(Some(b"p"), b"urn:other", &mut ns_buf);
+    assert_eq!(
+        r.event_namespace(QName(b"r:x"), &ns_buf).0,
+        Unknown(b"r".to_vec())
+    );
+    assert_eq!(
+        r.event_namespace(QName(b"p:x"), &ns_buf).0,
+        Bound(Namespace(b"urn:other"))
+    );
+    r.pop_namespace_scope(&mut ns_buf);
+
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Ok((ns, Empty(_))) => assert_eq!(ns, Bound(Namespace(b"urn:p"))),
+        e => panic!("expecting empty element, got {:?}", e),
+    }
+}
@@ -1,5 +1,6 @@
 use quick_xml::name::QName;
-use quick_xml::{events::attributes::Attribute, events::Event::*, Error, Reader};
+use quick_xml::reader::{BangType, EventMask, XmlSource};
+use quick_xml::{events::attributes::Attribute, events::Event::*, Error, Reader, Writer};
 use std::{borrow::Cow, io::Cursor};
 
 #[cfg(feature = "serialize")]
@@ -94,6 +95,98 @@ fn test_comment_starting_with_gt() {
     }
 }
 
+/// Processing instructions and comments inside the DOCTYPE internal subset
+/// should be skipped even when the DOCTYPE is read in small chunks
+#[test]
+fn test_doctype_with_pi_and_comment_in_chunks() {
+    let src = b"<!DOCTYPE e [<?pi > ?><!-- <<> --><!ELEMENT e ANY>]><e/>";
+    let mut r = Reader::from_reader(std::io::BufReader::with_capacity(3, src as &[u8]));
+    let mut buf = Vec::new();
+    match r.read_event_into(&mut buf) {
+        Ok(DocType(e)) => assert_eq!(
+            e.as_ref(),
+            b"e [<?pi > ?><!-- <<> --><!ELEMENT e ANY>]".as_ref()
+        ),
+        e => panic!("Expecting DocType event, got {:?}", e),
+    }
+    buf.clear();
+    match r.read_event_into(&mut buf) {
+        Ok(Empty(e)) => assert_eq!(e.name(), QName(b"e")),
+        e => panic!("Expecting Empty event, got {:?}", e),
+    }
+}
+
+#[test]
+fn test_bang_start_in_chunks() {
+    let src = b"<![CDATX[data]]>";
+    let mut r = Reader::from_reader(std::io::BufReader::with_capacity(3, src as &[u8]));
+    let mut buf = Vec::new();
+    match r.read_event_into(&mut buf) {
+        Err(Error::UnexpectedBang(b'X')) => {}
+        e => panic!("Expecting UnexpectedBang(b'X'), got {:?}", e),
+    }
+}
+
+/// Comments, read from an input that returns one byte at a time, should be
+/// parsed in the same way as comments, read from a whole buffer
+#[test]
+fn test_comments_in_one_byte_chunks() {
+    let docs: &[&[u8]] = &[
+        b"<!---->",
+        b"<!--a-->",
+        b"<!-- a - b -->",
+        b"<!--a->-->",
+        b"<!--a--->",
+        b"<!--a--b-->",
+        b"<!-->",
+        b"<!--->",
+        b"<!--a--",
+    ];
+    for doc in docs {
+        let mut whole = Reader::from_reader(*doc);
+        whole.check_comments(true);
+        let mut chunked = Reader::from_reader(std::io::BufReader::with_capacity(1, *doc));
+        chunked.check_comments(true);
+
+        let mut buf1 = Vec::new();
+        let mut buf2 = Vec::new();
+        let expected = whole.read_event_into(&mut buf1);
+        let actual = chunked.read_event_into(&mut buf2);
+        let doc = String::from_utf8_lossy(doc);
+        match (expected, actual) {
+            (Ok(expected), Ok(actual)) => assert_eq!(expected, actual, "{}", doc),
+            (Err(expected), Err(actual)) => {
+                assert_eq!(expected.to_string(), actual.to_string(), "{}", doc);
+                assert_eq!(
+                    whole.buffer_position(),
+                    chunked.buffer_position(),
+                    "{}",
+                    doc
+                );
+            }
+            (expected, actual) => panic!("{}: {:?} != {:?}", doc, expected, actual),
+        }
+    }
+}
+
+/// `BangType::parse` does not keep the state between calls, so the markup
+/// declarations of DOCTYPE, examined by the previous calls, are taken from `buf`
+#[test]
+fn test_bang_type_parse_doctype_in_chunks() {
+    let doc = b"!DOCTYPE e [<!-- > --><?pi > ?><!ENTITY x 'y'>]> rest";
+    let end = doc.len() - " rest".len();
+    for size in 1..doc.len() {
+        let bang_type = BangType::new(Some(doc[1]), 0).unwrap();
+        let mut buf = Vec::new();
+        let found = doc.chunks(size).find_map(|chunk| {
+            let found = bang_type.parse(&buf, chunk).unwrap();
+            buf.extend_from_slice(chunk);
+            found.map(|(_, i)| buf.len() - chunk.len() + i)
+        });
+        assert_eq!(found, Some(end), "chunk size {}", size);
+    }
+}
+
 #[test]
 #[cfg(feature = "encoding_rs")]
 fn test_koi8_r_encoding() {
@@ -174,6 +267,89 @@ fn fuzz_101() {
     }
 }
 
+/// Reads all events from `data` with buffered and borrowing readers,
+/// returning the first error
+fn read_all(data: &[u8], configure: fn(&mut Reader<&[u8]>)) -> Option<Error> {
+    let mut reader = Reader::from_reader(data);
+    configure(&mut reader);
+    let mut buf = vec![];
+    let buffered = loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Eof) => break None,
+            Err(e) => break Some(e),
+            _ => buf.clear(),
+        }
+    };
+
+    let mut reader = Reader::from_reader(data);
+    configure(&mut reader);
+    let borrowed = loop {
+        match reader.read_event() {
+            Ok(Eof) => break None,
+            Err(e) => break Some(e),
+            _ => (),
+        }
+    };
+    assert_eq!(
+        buffered.as_ref().map(|e| e.to_string()),
+        borrowed.as_ref().map(|e| e.to_string())
+    );
+    buffered
+}
+
+#[test]
+fn fuzz_doctype_without_name() {
+    match read_all(b"<!DOCTYPE>", |_| {}) {
+        Some(Error::EmptyDocType { position }) => assert_eq!(position, 0),
+        e => panic!("Expected `EmptyDocType` error, got {:?}", e),
+    }
+    match read_all(b"<a/><!doctype \n>", |_| {}) {
+        Some(Error::EmptyDocType { position }) => assert_eq!(position, 4),
+        e => panic!("Expected `EmptyDocType` error, got {:?}", e),
+    }
+}
+
+#[test]
+fn fuzz_unclosed_element() {
+    match read_all(b"<<?xml <!DOCTYPE\xFE\xFFb:c=", |r| {
+        r.check_names(true);
+    }) {
+        Some(Error::UnexpectedEof(what, _)) => assert_eq!(what, "Element"),
+        e => panic!("Expected `UnexpectedEof` error, got {:?}", e),
+    }
+}
+
+#[test]
+fn fuzz_unclosed_invalid_declaration() {
+    match read_all(b"<?xml SYSTEM/\r'?", |r| {
+        r.validate_declaration(true);
+    }) {
+        Some(Error::InvalidXmlDecl { position, .. }) => assert_eq!(position, 0),
+        e => panic!("Expected `InvalidXmlDecl` error, got {:?}", e),
+    }
+}
+
+#[test]
+fn fuzz_skip_events_with_start_text() {
+    let data = b"<!-- x --><a/>";
+    assert!(read_all(data, |r| {
+        r.skip_events(EventMask::COMMENT)
+            .always_emit_start_text(true);
+    })
+    .is_none());
+
+    // Events should borrow only the new data of the buffer
+    let mut reader = Reader::from_reader(data.as_ref());
+    reader
+        .skip_events(EventMask::COMMENT)
+        .always_emit_start_text(true);
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(b"old data");
+    assert!(matches!(reader.read_event_into(&mut buf), Ok(StartText(_))));
+    assert!(matches!(reader.read_event_into(&mut buf), Ok(Empty(_))));
+    assert!(matches!(reader.read_event_into(&mut buf), Ok(Eof)));
+}
+
 #[test]
 fn test_no_trim() {
     let mut reader = Reader::from_str(" <tag> text </tag> ");
@@ -226,6 +402,117 @@ fn test_clone_reader() {
     assert!(matches!(cloned.read_event_into(&mut buf).unwrap(), End(_)));
 }
 
+/// Fork should be independent from the original reader, including the stack
+/// of opened elements used for end names checking
+#[test]
+fn test_fork_reader() {
+    let mut reader = Reader::from_str("<outer><inner>text</inner></outer>");
+    assert!(matches!(reader.read_event().unwrap(), Start(_)));
+
+    let mut fork = reader.fork();
+    // Fork reads the subtree and the rest of document up to EOF
+    assert!(matches!(fork.read_event().unwrap(), Start(_)));
+    assert!(matches!(fork.read_event().unwrap(), Text(_)));
+    assert!(matches!(fork.read_event().unwrap(), End(_)));
+    assert!(matches!(fork.read_event().unwrap(), End(_)));
+    assert!(matches!(fork.read_event().unwrap(), Eof));
+
+    // Original still reads the subtree
+    match reader.read_event().unwrap() {
+        Start(e) => assert_eq!(e.name(), QName(b"inner")),
+        e => panic!("Expecting Start event, got {:?}", e),
+    }
+    assert!(matches!(reader.read_event().unwrap(), Text(_)));
+    match reader.read_event().unwrap() {
+        End(e) => assert_eq!(e.name(), QName(b"inner")),
+        e => panic!("Expecting End event, got {:?}", e),
+    }
+    match reader.read_event().unwrap() {
+        End(e) => assert_eq!(e.name(), QName(b"outer")),
+        e => panic!("Expecting End event, got {:?}", e),
+    }
+    assert!(matches!(reader.read_event().unwrap(), Eof));
+}
+
+/// A custom source that counts how many times the reader accessed it
+struct CountingSource<'a> {
+    inner: &'a [u8],
+    calls: usize,
+}
+
+impl<'a> XmlSource<'a, ()> for CountingSource<'a> {
+    fn read_bytes_until(
+        &mut self,
+        byte: u8,
+        buf: (),
+        position: &mut usize,
+    ) -> quick_xml::Result<Option<&'a [u8]>> {
+        self.calls += 1;
+        self.inner.read_bytes_until(byte, buf, position)
+    }
+
+    fn read_bang_element(
+        &mut self,
+        buf: (),
+        position: &mut usize,
+    ) -> quick_xml::Result<Option<(BangType, &'a [u8])>> {
+        self.calls += 1;
+        self.inner.read_bang_element(buf, position)
+    }
+
+    fn read_element(
+        &mut self,
+        buf: (),
+        position: &mut usize,
+    ) -> quick_xml::Result<Option<&'a [u8]>> {
+        self.calls += 1;
+        self.inner.read_element(buf, position)
+    }
+
+    fn skip_whitespace(&mut self, position: &mut usize) -> quick_xml::Result<()> {
+        XmlSource::<'a, ()>::skip_whitespace(&mut self.inner, position)
+    }
+
+    fn skip_one(&mut self, byte: u8, position: &mut usize) -> quick_xml::Result<bool> {
+        XmlSource::<'a, ()>::skip_one(&mut self.inner, byte, position)
+    }
+
+    fn peek_one(&mut self) -> quick_xml::Result<Option<u8>> {
+        XmlSource::<'a, ()>::peek_one(&mut self.inner)
+    }
+
+    fn peek_bytes(&mut self) -> quick_xml::Result<&[u8]> {
+        XmlSource::<'a, ()>::peek_bytes(&mut self.inner)
+    }
+}
+
+#[test]
+fn test_custom_source() {
+    let mut reader = Reader::from_reader(CountingSource {
+        inner: b"<tag>text<!--comment--></tag>",
+        calls: 0,
+    });
+    match reader.read_event_from_source(()).unwrap() {
+        Start(e) => assert_eq!(e.name(), QName(b"tag")),
+        e => panic!("Expecting Start event, got {:?}", e),
+    }
+    match reader.read_event_from_source(()).unwrap() {
+        Text(e) => assert_eq!(&*e, b"text"),
+        e => panic!("Expecting Text event, got {:?}", e),
+    }
+    match reader.read_event_from_source(()).unwrap() {
+        Comment(e) => assert_eq!(&*e, b"comment"),
+        e => panic!("Expecting Comment event, got {:?}", e),
+    }
+    match reader.read_event_from_source(()).unwrap() {
+        End(e) => assert_eq!(e.name(), QName(b"tag")),
+        e => panic!("Expecting End event, got {:?}", e),
+    }
+    assert!(matches!(reader.read_event_from_source(()).unwrap(), Eof));
+    assert_eq!(reader.buffer_position(), 29);
+    assert!(reader.get_ref().calls > 0);
+}
+
 #[cfg(feature = "serialize")]
 #[test]
 fn line_score() {
@@ -1010,3 +1297,48 @@ fn test_issue305_unflatten_nesting() -> Result<(), quick_xml::DeError> {
 
     Ok(())
 }
+
+/// Writing all events, read with `preserve_raw`, should produce the original document
+#[test]
+fn preserve_raw_round_trip() {
+    let xml = include_str!("documents/players.xml");
+    let mut reader = Reader::from_str(xml);
+    reader
+        .trim_text(true)
+        .expand_empty_elements(true)
+        .preserve_raw(true);
+
+    let mut writer = Writer::new(Vec::new());
+    loop {
+        match reader.read_event().unwrap() {
+            Eof => break,
+            e => writer.write_event(e).unwrap(),
+        }
+    }
+    assert_eq!(std::str::from_utf8(&writer.into_inner()).unwrap(), xml);
+}
+
+/// `End` events, read with `preserve_raw`, keep whitespaces after the name,
+/// but report the name without them
+#[test]
+fn preserve_raw_end_name() {
+    let mut reader = Reader::from_str("<a><b></b \t></a\n>");
+    reader.preserve_raw(true);
+
+    assert!(matches!(reader.read_event().unwrap(), Start(_)));
+    assert!(matches!(reader.read_event().unwrap(), Start(_)));
+    match reader.read_event().unwrap() {
+        End(e) => {
+            assert_eq!(e.name(), QName(b"b"));
+            assert_eq!(&*e, b"b \t");
+        }
+        e => panic!("Expecting End(b), found {:?}", e),
+    }
+    match reader.read_event().unwrap() {
+        End(e) => {
+            assert_eq!(e.local_name().as_ref(), b"a");
+            assert_eq!(&*e, b"a\n");
+        }
+        e => panic!("Expecting End(a), found {:?}", e),
+    }
+}
@@ -1,10 +1,11 @@
 use std::borrow::Cow;
-use std::io::Cursor;
+use std::io::{BufRead, BufReader, Cursor};
 use std::str::from_utf8;
 
 use quick_xml::events::attributes::{AttrError, Attribute};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::name::QName;
+use quick_xml::reader::EventMask;
 use quick_xml::{events::Event::*, Reader, Result, Writer};
 
 use pretty_assertions::assert_eq;
@@ -172,6 +173,53 @@ fn test_trim_test() {
     next_eq!(r, Start, b"a", Start, b"b", Text, b"  ", End, b"b", End, b"a");
 }
 
+#[test]
+fn test_last_trimmed() {
+    let mut r = Reader::from_str(" \t start <a>\n  text\n</a> <b>x </b>");
+    r.trim_text(true);
+    next_eq!(r, StartText, b"start");
+    assert_eq!(r.last_trimmed(), (3, 1));
+    next_eq!(r, Start, b"a");
+    assert_eq!(r.last_trimmed(), (0, 0));
+    next_eq!(r, Text, b"text");
+    assert_eq!(r.last_trimmed(), (3, 1));
+    // whitespace-only text between `</a>` and `<b>` is skipped
+    next_eq!(r, End, b"a", Start, b"b");
+    assert_eq!(r.last_trimmed(), (0, 0));
+
+    r.trim_text(false).trim_text_end(true);
+    next_eq!(r, Text, b"x");
+    assert_eq!(r.last_trimmed(), (0, 1));
+
+    let mut r = Reader::from_str("<a> text </a>");
+    next_eq!(r, Start, b"a", Text, b" text ");
+    assert_eq!(r.last_trimmed(), (0, 0));
+}
+
+/// Settings that are documented as safe to change between events should take
+/// effect starting from the next event
+#[test]
+fn test_change_settings_while_reading() {
+    let mut r = Reader::from_str("<a> x <b/><c/><d > y </d ></a>");
+    next_eq!(r, Start, b"a", Text, b" x ");
+
+    r.trim_text(true).expand_empty_elements(true);
+    next_eq!(r, Start, b"b");
+    // The `End` event of the already split element is still emitted
+    r.expand_empty_elements(false);
+    next_eq!(r, End, b"b", Empty, b"c", Start, b"d");
+
+    r.trim_text(false);
+    next_eq!(r, Text, b" y ", End, b"d");
+
+    // `check_end_names` is only set before the first read
+    let mut r = Reader::from_str("<a><b ></b ></a >");
+    r.check_end_names(false);
+    next_eq!(r, Start, b"a", Start, b"b", End, b"b");
+    r.trim_markup_names_in_closing_tags(false);
+    next_eq!(r, End, b"a ");
+}
+
 #[test]
 fn test_cdata() {
     let mut r = Reader::from_str("<![CDATA[test]]>");
@@ -179,6 +227,221 @@ fn test_cdata() {
     next_eq!(r, CData, b"test");
 }
 
+/// Events read from an unbuffered source should be the same as from a buffered one
+#[test]
+fn test_from_unbuffered_reader() {
+    let path = "tests/documents/sample_1.xml";
+    let mut expected = Reader::from_file(path).unwrap();
+    let mut r1 = Reader::from_unbuffered_reader(std::fs::File::open(path).unwrap());
+    let mut r2 =
+        Reader::from_unbuffered_reader_with_capacity(1, std::fs::File::open(path).unwrap());
+
+    let mut buf = Vec::new();
+    let mut buf1 = Vec::new();
+    let mut buf2 = Vec::new();
+    loop {
+        let event = expected.read_event_into(&mut buf).unwrap();
+        assert_eq!(r1.read_event_into(&mut buf1).unwrap(), event);
+        assert_eq!(r2.read_event_into(&mut buf2).unwrap(), event);
+        if event == Eof {
+            break;
+        }
+        buf.clear();
+        buf1.clear();
+        buf2.clear();
+    }
+    assert_eq!(r2.get_ref().capacity(), 1);
+}
+
+#[test]
+fn test_require_root_element() {
+    for xml in ["", "  \n\t", "<!-- comment --><?pi?>"] {
+        // Documents without elements are allowed by default
+        let mut r = Reader::from_str(xml);
+        loop {
+            match r.read_event() {
+                Ok(Eof) => break,
+                Ok(_) => {}
+                Err(e) => panic!("Unexpected error for {:?}: {:?}", xml, e),
+            }
+        }
+
+        let mut r = Reader::from_str(xml);
+        r.require_root_element(true);
+        loop {
+            match r.read_event() {
+                Err(quick_xml::Error::NoRootElement) => break,
+                Ok(Eof) => panic!("Expected `NoRootElement` error for {:?}", xml),
+                Ok(_) => {}
+                Err(e) => panic!("Unexpected error for {:?}: {:?}", xml, e),
+            }
+        }
+        // The error is reported only once
+        assert_eq!(r.read_event().unwrap(), Eof);
+    }
+
+    for xml in ["<root/>", "<!-- comment --><root></root>"] {
+        let mut r = Reader::from_str(xml);
+        r.require_root_element(true);
+        while r.read_event().unwrap() != Eof {}
+    }
+}
+
+#[test]
+fn test_max_event_size() {
+    // Each event has at most 4 bytes of the raw content
+    let xml = "<tag>text</tag>cdef<?pi?><ab/>";
+
+    /// Reads all events with the given limit from the buffered and the slice
+    /// readers and returns the position of the error, if any
+    fn read(xml: &str, limit: usize) -> Option<usize> {
+        let mut buffered = Reader::from_reader(BufReader::with_capacity(2, xml.as_bytes()));
+        buffered.max_event_size(limit);
+        let mut buf = Vec::new();
+        let buffered = loop {
+            buf.clear();
+            match buffered.read_event_into(&mut buf) {
+                Ok(Eof) => break None,
+                Ok(_) => {}
+                Err(quick_xml::Error::EventTooLarge { limit: l, position }) => {
+                    assert_eq!(l, limit);
+                    // The buffer does not grow over the limit
+                    assert!(buf.len() <= limit + 1, "{} > {}", buf.len(), limit + 1);
+                    break Some(position);
+                }
+                Err(e) => panic!("Unexpected error {:?}", e),
+            }
+        };
+
+        let mut slice = Reader::from_str(xml);
+        slice.max_event_size(limit);
+        let slice = loop {
+            match slice.read_event() {
+                Ok(Eof) => break None,
+                Ok(_) => {}
+                Err(quick_xml::Error::EventTooLarge { position, .. }) => break Some(position),
+                Err(e) => panic!("Unexpected error {:?}", e),
+            }
+        };
+        assert_eq!(buffered, slice, "{:?}", xml);
+        buffered
+    }
+
+    assert_eq!(read(xml, 4), None);
+    assert_eq!(read(xml, 3), Some(5));
+    assert_eq!(read("<tag>", 2), Some(1));
+    assert_eq!(read("<a/><!-- comment -->", 4), Some(5));
+    assert_eq!(read("<a/><![CDATA[content]]>", 10), Some(5));
+    assert_eq!(read("<a></abc>", 2), Some(4));
+    assert_eq!(read("<?target?>", 4), Some(1));
+
+    // Chunked CDATA is not limited, but comments are
+    let mut r = Reader::from_str("<![CDATA[content]]><!-- comment -->");
+    r.max_event_size(4).cdata_chunk_size(Some(4));
+    assert!(matches!(r.read_event(), Ok(CData(_))));
+    assert!(matches!(r.read_event(), Ok(CData(_))));
+    match r.read_event() {
+        Err(quick_xml::Error::EventTooLarge { limit, position }) => {
+            assert_eq!((limit, position), (4, 20))
+        }
+        e => panic!("Expected `EventTooLarge` error, got {:?}", e),
+    }
+}
+
+#[test]
+fn test_auto_clear_buffer() {
+    let xml = b"<a attr='value'><b/>text</a>";
+    let mut r = Reader::from_reader(&xml[..]);
+    r.auto_clear_buffer(true);
+
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        match r.read_event_into(&mut buf).unwrap() {
+            Eof => break,
+            e => events.push(e.into_owned()),
+        }
+    }
+    assert_eq!(
+        events,
+        vec![
+            Start(BytesStart::borrowed(b"a attr='value'", 1)),
+            Empty(BytesStart::borrowed_name(b"b")),
+            Text(BytesText::from_escaped(&b"text"[..])),
+            End(BytesEnd::borrowed(b"a")),
+        ]
+    );
+    assert!(buf.is_empty());
+
+    // Without the flag content of events is accumulated
+    let mut r = Reader::from_reader(&xml[..]);
+    let mut buf = Vec::new();
+    while r.read_event_into(&mut buf).unwrap() != Eof {}
+    assert_eq!(buf, b"a attr='value'b/text/a");
+}
+
+/// Parts of CDATA content should form the whole content regardless of how
+/// the input is split into buffers of the underlying reader
+#[test]
+fn test_cdata_chunk_size() {
+    let xml = b"<a><![CDATA[x]]]><c/><![CDATA[]] >]]]]></a><![CDATA[]]><b/>";
+    let expected = ["x]", "]] >]]", ""];
+    for capacity in 1..xml.len() {
+        for size in 3..10 {
+            let mut r = Reader::from_reader(std::io::BufReader::with_capacity(capacity, &xml[..]));
+            r.cdata_chunk_size(Some(size));
+
+            let mut buf = Vec::new();
+            let mut sections = Vec::new();
+            let mut current: Option<Vec<u8>> = None;
+            loop {
+                match r.read_event_into(&mut buf).unwrap() {
+                    CData(e) => {
+                        assert!(e.len() <= size, "{:?}", e);
+                        current.get_or_insert_with(Vec::new).extend_from_slice(&e);
+                    }
+                    Eof => break,
+                    _ => sections.extend(current.take()),
+                }
+                buf.clear();
+            }
+            let sections: Vec<_> = sections.iter().map(|s| from_utf8(s).unwrap()).collect();
+            assert_eq!(sections, expected, "capacity {}, size {}", capacity, size);
+        }
+    }
+}
+
+/// Postponed `]` should not produce an empty part, when they are the beginning
+/// of the terminator, split between buffers of the underlying reader
+#[test]
+fn test_cdata_chunk_size_split_terminator() {
+    for &xml in &[
+        &b"<![CDATA[01]]>"[..],
+        b"<![CDATA[0]]]]>",
+        b"<![CDATA[0]]]]]]]>",
+    ] {
+        for capacity in 1..7 {
+            let mut r = Reader::from_reader(BufReader::with_capacity(capacity, xml));
+            r.cdata_chunk_size(Some(3));
+
+            let mut buf = Vec::new();
+            let mut content = Vec::new();
+            loop {
+                match r.read_event_into(&mut buf).unwrap() {
+                    CData(e) => {
+                        assert!(!e.is_empty(), "capacity {}, {:?}", capacity, content);
+                        content.extend_from_slice(&e);
+                    }
+                    Eof => break,
+                    e => panic!("unexpected event {:?}", e),
+                }
+                buf.clear();
+            }
+            assert_eq!(content, &xml[9..xml.len() - 3], "capacity {}", capacity);
+        }
+    }
+}
+
 #[test]
 fn test_cdata_open_close() {
     let mut r = Reader::from_str("<![CDATA[test <> test]]>");
@@ -782,6 +1045,1368 @@ fn test_closing_bracket_in_single_quote_mixed() {
     next_eq!(r, End, b"a");
 }
 
+#[test]
+fn test_for_each_to_end() {
+    let mut r = Reader::from_str("<a><b/>text<a></a></a><c/>");
+    let mut buf = Vec::new();
+    next_eq!(r, Start, b"a");
+
+    let mut events = Vec::new();
+    r.for_each_to_end(QName(b"a"), &mut buf, |e| {
+        events.push(e.clone().into_owned())
+    })
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            Empty(BytesStart::borrowed_name(b"b")),
+            Text(BytesText::from_escaped(b"text".as_ref())),
+            Start(BytesStart::borrowed_name(b"a")),
+            End(BytesEnd::borrowed(b"a")),
+            End(BytesEnd::borrowed(b"a")),
+        ]
+    );
+    next_eq!(r, Empty, b"c");
+}
+
+#[test]
+fn test_for_each_to_end_eof() {
+    let mut r = Reader::from_str("<a><a></a><b>");
+    let mut buf = Vec::new();
+    next_eq!(r, Start, b"a");
+
+    let mut count = 0;
+    match r.for_each_to_end(QName(b"a"), &mut buf, |_| count += 1) {
+        Err(quick_xml::Error::UnexpectedEof(..)) => {}
+        x => panic!("expected Err(UnexpectedEof), got {:?}", x),
+    }
+    assert_eq!(count, 3);
+}
+
+fn rename_old(name: &[u8]) -> Option<Cow<'_, [u8]>> {
+    match name {
+        b"old" => Some(Cow::Borrowed(b"new")),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_map_names() {
+    let mut r = Reader::from_str("<root><old a='1'><old/></old><other/></root>");
+    r.map_names(rename_old);
+
+    next_eq!(r, Start, b"root");
+    match r.read_event().unwrap() {
+        Start(e) => {
+            assert_eq!(e.name(), QName(b"new"));
+            assert_eq!(
+                e.attributes().next().unwrap().unwrap(),
+                Attribute::from(("a", "1"))
+            );
+        }
+        e => panic!("expecting Start, found {:?}", e),
+    }
+    next_eq!(r, Empty, b"new", End, b"new", Empty, b"other", End, b"root");
+}
+
+/// Skipping events in the buffered reader should not lose names, that are
+/// not borrowed from the input
+#[test]
+fn test_map_names_skip_events() {
+    let mut r = Reader::from_reader(b"<old><!-- x --></old>".as_ref());
+    r.map_names(rename_old).skip_events(EventMask::COMMENT);
+
+    next_eq!(r, Start, b"new", End, b"new");
+}
+
+#[test]
+fn test_skip_events() {
+    let xml = "<?xml version='1.0'?><!DOCTYPE root><root>\n  <!-- comment -->\n  \
+               <?pi?><![CDATA[cdata]]>text<child/>\n</root>";
+
+    let mut r = Reader::from_str(xml);
+    r.skip_events(
+        EventMask::DECL
+            | EventMask::DOCTYPE
+            | EventMask::COMMENT
+            | EventMask::PI
+            | EventMask::BLANK_TEXT,
+    );
+    next_eq!(r, Start, b"root", CData, b"cdata", Text, b"text", Empty, b"child", End, b"root");
+
+    // Buffered reader
+    let mut r = Reader::from_reader(xml.as_bytes());
+    r.skip_events(EventMask::DECL | EventMask::DOCTYPE | EventMask::TEXT | EventMask::CDATA);
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        match r.read_event_into(&mut buf).unwrap() {
+            Eof => break,
+            e => events.push(e.into_owned()),
+        }
+    }
+    assert_eq!(
+        events,
+        vec![
+            Start(BytesStart::borrowed_name(b"root")),
+            Comment(BytesText::from_escaped_str(" comment ")),
+            PI(BytesText::from_escaped_str("pi")),
+            Empty(BytesStart::borrowed_name(b"child")),
+            End(BytesEnd::borrowed(b"root")),
+        ]
+    );
+}
+
+/// An empty `StartText` can be returned while other events are skipped,
+/// even if the buffer already contains some data
+#[test]
+fn test_skip_events_always_emit_start_text() {
+    let mut r = Reader::from_str("<!-- x --><a/>");
+    r.skip_events(EventMask::COMMENT)
+        .always_emit_start_text(true);
+    next_eq!(r, StartText, b"", Empty, b"a");
+
+    let mut r = Reader::from_reader(b"<!-- x --><a/>".as_ref());
+    r.skip_events(EventMask::COMMENT)
+        .always_emit_start_text(true);
+    let mut buf = b"data".to_vec();
+    assert_eq!(
+        r.read_event_into(&mut buf).unwrap(),
+        StartText(BytesText::from_escaped_str("").into())
+    );
+    assert_eq!(
+        r.read_event_into(&mut buf).unwrap(),
+        Empty(BytesStart::borrowed_name(b"a"))
+    );
+}
+
+/// Skipped events do not prevent `read_to_end` from finding the end
+#[test]
+fn test_skip_events_read_to_end() {
+    let mut r = Reader::from_reader(b"<a><b><!--c--></b>text</a><d/>".as_ref());
+    r.skip_events(EventMask::COMMENT | EventMask::TEXT);
+    let mut buf = Vec::new();
+
+    next_eq!(r, Start, b"a");
+    r.read_to_end_into(QName(b"a"), &mut buf).unwrap();
+    next_eq!(r, Empty, b"d");
+}
+
+#[test]
+fn test_read_to_end_strict() {
+    let mut buf = Vec::new();
+
+    let mut r = Reader::from_str("<a><b><a></a><c/></b></a><d/>");
+    next_eq!(r, Start, b"a");
+    r.read_to_end_strict(QName(b"a"), &mut buf).unwrap();
+    next_eq!(r, Empty, b"d");
+
+    // Mismatches are found even if the names of end tags are not checked,
+    // and reported at the same position as with checked names
+    for (xml, expected, found, position) in [
+        ("<a><b></c></a>", "b", "c", 8),
+        ("<a><b></a></b>", "b", "a", 8),
+        ("<a></b>", "a", "b", 5),
+    ] {
+        let mut r = Reader::from_str(xml);
+        r.check_end_names(false);
+        next_eq!(r, Start, b"a");
+        match r.read_to_end_strict(QName(b"a"), &mut buf) {
+            Err(quick_xml::Error::EndEventMismatch {
+                expected: e,
+                found: f,
+                position: p,
+            }) => assert_eq!(
+                (e.as_str(), f.as_str(), p),
+                (expected, found, position),
+                "{}",
+                xml
+            ),
+            x => panic!("Expected `EndEventMismatch` for {}, got {:?}", xml, x),
+        }
+    }
+
+    let mut r = Reader::from_str("<a><b></b>");
+    next_eq!(r, Start, b"a");
+    match r.read_to_end_strict(QName(b"a"), &mut buf) {
+        Err(quick_xml::Error::UnexpectedEof(s, _)) => assert_eq!(s, "</a>"),
+        x => panic!("Expected `UnexpectedEof`, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_map_names_expand_empty() {
+    let mut r = Reader::from_str("<old/>");
+    r.map_names(rename_old).expand_empty_elements(true);
+
+    next_eq!(r, Start, b"new", End, b"new");
+}
+
+/// Names are compared after transformation
+#[test]
+fn test_map_names_check_end_names() {
+    let mut r = Reader::from_str("<old></new>");
+    r.map_names(rename_old);
+
+    next_eq!(r, Start, b"new", End, b"new");
+
+    let mut r = Reader::from_str("<old></old-tag>");
+    r.map_names(rename_old);
+
+    next_eq!(r, Start, b"new");
+    match r.read_event() {
+        Err(quick_xml::Error::EndEventMismatch {
+            expected, found, ..
+        }) => {
+            assert_eq!(expected, "new");
+            assert_eq!(found, "old-tag");
+        }
+        e => panic!("expecting EndEventMismatch, found {:?}", e),
+    }
+
+    let mut r = Reader::from_str("</old>");
+    r.map_names(rename_old);
+
+    match r.read_event() {
+        Err(quick_xml::Error::EndEventMismatch {
+            expected, found, ..
+        }) => {
+            assert_eq!(expected, "");
+            assert_eq!(found, "new");
+        }
+        e => panic!("expecting EndEventMismatch, found {:?}", e),
+    }
+}
+
+#[test]
+fn test_reset_after_eof() {
+    let mut r = Reader::from_reader(Cursor::new(Vec::new()));
+    let mut buf = Vec::new();
+    assert_eq!(r.read_event_into(&mut buf).unwrap(), Eof);
+
+    // Nothing was read, so the first event could still be a `StartText`
+    r.get_mut().get_mut().extend_from_slice(b"text<a>");
+    r.reset_after_eof();
+    next_eq!(r, StartText, b"text", Start, b"a");
+    assert_eq!(r.read_event_into(&mut buf).unwrap(), Eof);
+
+    r.get_mut().get_mut().extend_from_slice(b"</a>");
+    assert_eq!(r.read_event_into(&mut buf).unwrap(), Eof);
+    r.reset_after_eof();
+    next_eq!(r, End, b"a");
+    assert_eq!(r.read_event_into(&mut buf).unwrap(), Eof);
+}
+
+#[test]
+fn test_root_element_name() {
+    use quick_xml::root_element_name;
+
+    let root =
+        root_element_name(b"<?xml version='1.0'?><!DOCTYPE html [<!ENTITY e 'v'>]><?pi?><html>")
+            .unwrap()
+            .unwrap();
+    assert_eq!(root.name(), QName(b"html"));
+
+    let root = root_element_name(b"<!-- comment --><feed:feed xmlns:feed='urn:feed'/>")
+        .unwrap()
+        .unwrap();
+    assert_eq!(root.name(), QName(b"feed:feed"));
+    assert_eq!(root.attributes().count(), 1);
+
+    assert!(root_element_name(b"").unwrap().is_none());
+    assert!(root_element_name(b"<!-- unclosed").is_err());
+}
+
+#[test]
+fn test_duplicate_attributes() {
+    let mut r = Reader::from_str("<a x='1' y='2'><b x='1' y='2' x='3'/></a>");
+    next_eq!(r, Start, b"a", Empty, b"b", End, b"a");
+
+    let mut r = Reader::from_str("<a x='1' y='2'><b x='1' y='2' x='3'/></a>");
+    r.check_duplicate_attributes(true);
+    next_eq!(r, Start, b"a");
+    match r.read_event() {
+        Err(quick_xml::Error::DuplicateAttribute { name, position }) => {
+            assert_eq!(name, "x");
+            assert_eq!(position, 30);
+            assert_eq!(r.buffer_position(), 30);
+        }
+        e => panic!("expecting DuplicateAttribute, found {:?}", e),
+    }
+}
+
+#[test]
+fn test_read_event_with_span() {
+    let xml = "<?xml version='1.0'?>\n<root>\n  <a/>  text  <!--c-->\n</root>\n";
+    let mut r = Reader::from_reader(xml.as_bytes());
+    r.trim_text(true).expand_empty_elements(true);
+    let mut buf = Vec::new();
+
+    let mut spans = Vec::new();
+    loop {
+        let (event, span) = r.read_event_with_span(&mut buf).unwrap();
+        if event == Eof {
+            // Trailing whitespaces were skipped
+            assert_eq!(&xml[span], "\n");
+            break;
+        }
+        spans.push(&xml[span]);
+    }
+    assert_eq!(
+        spans,
+        vec![
+            "<?xml version='1.0'?>",
+            "\n<root>",
+            "\n  <a/>",
+            "",
+            "  text  ",
+            "<!--c-->",
+            "\n</root>",
+        ]
+    );
+}
+
+#[test]
+fn test_comment_check() {
+    use quick_xml::reader::CommentCheck;
+
+    let xml = "<a><!--a--b--><!--ok--><!--c---></a>";
+
+    let mut r = Reader::from_str(xml);
+    r.comment_check(CommentCheck::Warn);
+    next_eq!(r, Start, b"a", Comment, b"a--b", Comment, b"ok", Comment, b"c-", End, b"a");
+    assert_eq!(r.take_comment_warnings(), vec![8, 28]);
+
+    let mut r = Reader::from_str(xml);
+    r.comment_check(CommentCheck::Error);
+    next_eq!(r, Start, b"a");
+    match r.read_event() {
+        Err(quick_xml::Error::UnexpectedToken(token)) => assert_eq!(token, "--"),
+        e => panic!("expecting UnexpectedToken, found {:?}", e),
+    }
+    assert_eq!(r.buffer_position(), 8);
+    assert_eq!(r.take_comment_warnings(), Vec::<usize>::new());
+
+    let mut r = Reader::from_str(xml);
+    r.comment_check(CommentCheck::Off);
+    next_eq!(r, Start, b"a", Comment, b"a--b", Comment, b"ok", Comment, b"c-", End, b"a");
+    assert_eq!(r.take_comment_warnings(), Vec::<usize>::new());
+}
+
+#[test]
+fn test_normalize_comments() {
+    let xml = "<a><!-- a  b --><!--\t\r\n--><!--c--></a>";
+
+    let mut r = Reader::from_str(xml);
+    next_eq!(r, Start, b"a", Comment, b" a  b ", Comment, b"\t\r\n", Comment, b"c", End, b"a");
+
+    let mut r = Reader::from_str(xml);
+    r.normalize_comments(true);
+    next_eq!(r, Start, b"a", Comment, b"a  b", Comment, b"", Comment, b"c", End, b"a");
+
+    // Raw content is not changed
+    let mut r = Reader::from_str(xml);
+    r.normalize_comments(true).preserve_raw(true);
+    next_eq!(r, Start, b"a", Comment, b" a  b ", Comment, b"\t\r\n", Comment, b"c", End, b"a");
+}
+
+#[test]
+fn test_cdata_as_text() {
+    let xml = "<a>x<![CDATA[ <b>&amp; ]]>y</a>";
+
+    let mut r = Reader::from_str(xml);
+    r.trim_text(true).cdata_as_text(true);
+    next_eq!(
+        r,
+        Start,
+        b"a",
+        Text,
+        b"x",
+        Text,
+        b" &lt;b&gt;&amp;amp; ",
+        Text,
+        b"y",
+        End,
+        b"a"
+    );
+
+    // Unescaped content is the same as the content of the CDATA section
+    let mut r = Reader::from_reader(xml.as_bytes());
+    r.cdata_as_text(true);
+    let mut buf = Vec::new();
+    r.read_event_into(&mut buf).unwrap();
+    r.read_event_into(&mut buf).unwrap();
+    match r.read_event_into(&mut buf).unwrap() {
+        Text(e) => assert_eq!(&*e.unescaped().unwrap(), b" <b>&amp; "),
+        e => panic!("Expected `Text` event, got {:?}", e),
+    }
+
+    // Parts of chunked sections are converted separately
+    let mut r = Reader::from_str("<![CDATA[<>&<>]]>");
+    r.cdata_as_text(true).cdata_chunk_size(Some(3));
+    next_eq!(r, Text, b"&lt;&gt;&amp;", Text, b"&lt;&gt;");
+
+    // Raw content is not changed
+    let mut r = Reader::from_str(xml);
+    r.cdata_as_text(true).preserve_raw(true);
+    next_eq!(
+        r,
+        Start,
+        b"a",
+        Text,
+        b"x",
+        CData,
+        b" <b>&amp; ",
+        Text,
+        b"y",
+        End,
+        b"a"
+    );
+}
+
+#[test]
+fn test_strict_declaration_position() {
+    fn check(xml: &[u8], trim: bool) -> Result<()> {
+        let mut r = Reader::from_reader(xml);
+        r.trim_text(trim).strict_declaration_position(true);
+        let mut buf = Vec::new();
+        loop {
+            if r.read_event_into(&mut buf)? == Eof {
+                return Ok(());
+            }
+            buf.clear();
+        }
+    }
+    fn misplaced(xml: &[u8], trim: bool) -> Option<usize> {
+        match check(xml, trim) {
+            Err(quick_xml::Error::MisplacedDeclaration { position }) => Some(position),
+            Ok(()) => None,
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    assert_eq!(misplaced(b"<?xml version='1.0'?><a/>", false), None);
+    assert_eq!(
+        misplaced(b"\xEF\xBB\xBF<?xml version='1.0'?><a/>", false),
+        None
+    );
+    assert_eq!(misplaced(b"<a/>", false), None);
+
+    // Whitespaces are reported even if they are trimmed
+    assert_eq!(misplaced(b"  <?xml version='1.0'?><a/>", false), Some(2));
+    assert_eq!(misplaced(b"  <?xml version='1.0'?><a/>", true), Some(2));
+
+    // Declaration after other events
+    assert_eq!(misplaced(b"<!----><?xml version='1.0'?>", false), Some(7));
+    assert_eq!(
+        misplaced(b"<?xml version='1.0'?><?xml version='1.0'?>", false),
+        Some(21)
+    );
+
+    // Not checked by default
+    let mut r = Reader::from_str("  <?xml version='1.0'?>");
+    r.trim_text(true);
+    assert!(matches!(r.read_event().unwrap(), Decl(_)));
+}
+
+#[test]
+fn test_error_on_read_after_eof() {
+    let mut r = Reader::from_str("<a/>");
+    r.error_on_read_after_eof(true);
+    next_eq!(r, Empty, b"a");
+    assert_eq!(r.read_event().unwrap(), Eof);
+    assert!(matches!(
+        r.read_event(),
+        Err(quick_xml::Error::ReadAfterEof)
+    ));
+    assert!(matches!(
+        r.read_event(),
+        Err(quick_xml::Error::ReadAfterEof)
+    ));
+
+    // `Eof` after an error is returned once
+    let mut r = Reader::from_str("<a></b><c/>");
+    r.error_on_read_after_eof(true);
+    next_eq!(r, Start, b"a");
+    assert!(matches!(
+        r.read_event(),
+        Err(quick_xml::Error::EndEventMismatch { .. })
+    ));
+    assert_eq!(r.read_event().unwrap(), Eof);
+    assert!(matches!(
+        r.read_event(),
+        Err(quick_xml::Error::ReadAfterEof)
+    ));
+
+    // Reading can be continued after a resync
+    r.resync().unwrap();
+    next_eq!(r, Empty, b"c");
+    assert_eq!(r.read_event().unwrap(), Eof);
+    assert!(matches!(
+        r.read_event(),
+        Err(quick_xml::Error::ReadAfterEof)
+    ));
+
+    // Not checked by default
+    let mut r = Reader::from_str("");
+    assert_eq!(r.read_event().unwrap(), Eof);
+    assert_eq!(r.read_event().unwrap(), Eof);
+}
+
+#[test]
+fn test_error_on_unexpected_eof() {
+    fn read(xml: &str, configure: fn(&mut Reader<&[u8]>)) -> Result<()> {
+        let mut r = Reader::from_str(xml);
+        r.error_on_unexpected_eof(true);
+        configure(&mut r);
+        while r.read_event()? != Eof {}
+        Ok(())
+    }
+    fn unclosed(xml: &str, configure: fn(&mut Reader<&[u8]>)) -> Option<(String, usize)> {
+        match read(xml, configure) {
+            Ok(()) => None,
+            Err(quick_xml::Error::UnexpectedEof(what, position)) => Some((what, position)),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    assert_eq!(unclosed("<a><b/></a>", |_| {}), None);
+    assert_eq!(unclosed("<a/><!-- -->", |_| {}), None);
+    assert_eq!(
+        unclosed("<a><b>text", |_| {}),
+        Some(("</b></a>".to_string(), 10))
+    );
+    assert_eq!(
+        unclosed("<a><b></b>", |r| {
+            r.trim_text(true);
+        }),
+        Some(("</a>".to_string(), 10))
+    );
+    // Open elements are not tracked without checking of end names
+    assert_eq!(
+        unclosed("<a><b>", |r| {
+            r.check_end_names(false);
+        }),
+        None
+    );
+
+    // Not checked by default
+    let mut r = Reader::from_str("<a>");
+    next_eq!(r, Start, b"a");
+    assert_eq!(r.read_event().unwrap(), Eof);
+}
+
+#[test]
+fn test_names_as_str() -> Result<()> {
+    let mut r = Reader::from_str("<p:root><item/></p:root>");
+    let mut names = Vec::new();
+    loop {
+        match r.read_event()? {
+            Start(e) | Empty(e) => match e.name().as_str()? {
+                "p:root" => names.push(format!("{}", e.local_name())),
+                "item" => names.push(e.name().to_string()),
+                name => panic!("Unexpected element `{}`", name),
+            },
+            Eof => break,
+            _ => {}
+        }
+    }
+    assert_eq!(names, vec!["root", "item"]);
+
+    let name = QName(b"p:\xFF");
+    assert!(name.as_str().is_err());
+    assert!(name.local_name().as_str().is_err());
+    assert_eq!(name.to_string(), "p:\u{FFFD}");
+    assert_eq!(name.local_name().to_string(), "\u{FFFD}");
+    Ok(())
+}
+
+#[test]
+fn test_entities() {
+    let mut entities = std::collections::HashMap::new();
+    entities.insert(b"sp".to_vec(), b"\xC2\xA0".to_vec());
+    entities.insert(b"product".to_vec(), b"quick-xml".to_vec());
+
+    let mut r = Reader::from_str("<a b='&product;&lt;'>&product;&sp;&amp;</a><c>&unknown;</c>");
+    r.entities(entities.clone());
+    assert!(r.config().entities);
+
+    match r.read_event().unwrap() {
+        Start(e) => {
+            let attr = e.attributes().next().unwrap().unwrap();
+            assert_eq!(attr.unescape_and_decode_value(&r).unwrap(), "quick-xml<");
+        }
+        e => panic!("Expected `Start` event, got {:?}", e),
+    }
+    match r.read_event().unwrap() {
+        Text(e) => {
+            assert_eq!(e.unescape_and_decode(&r).unwrap(), "quick-xml\u{A0}&");
+
+            // Explicitly passed entities are used instead of the reader ones
+            entities.remove(&b"sp"[..]);
+            assert!(e
+                .unescape_and_decode_with_custom_entities(&r, &entities)
+                .is_err());
+        }
+        e => panic!("Expected `Text` event, got {:?}", e),
+    }
+    next_eq!(r, End, b"a", Start, b"c");
+    // Unknown entities are still errors
+    match r.read_event().unwrap() {
+        Text(e) => assert!(e.unescape_and_decode(&r).is_err()),
+        e => panic!("Expected `Text` event, got {:?}", e),
+    }
+}
+
+#[test]
+fn test_statistics() {
+    use quick_xml::reader::Stats;
+
+    let xml = "<?xml version='1.0'?><!DOCTYPE root><root>text<![CDATA[x]]><a/>\
+               <!--comment--><?pi?></root>";
+
+    let mut r = Reader::from_str(xml);
+    while r.read_event().unwrap() != Eof {}
+    assert_eq!(r.statistics(), Stats::default());
+
+    let mut r = Reader::from_str(xml);
+    r.collect_stats(true)
+        .expand_empty_elements(true)
+        .skip_events(EventMask::COMMENT);
+    while r.read_event().unwrap() != Eof {}
+    assert_eq!(
+        r.statistics(),
+        Stats {
+            start: 2,
+            end: 2,
+            empty: 0,
+            text: 1,
+            cdata: 1,
+            comment: 1,
+            pi: 1,
+            decl: 1,
+            doctype: 1,
+        }
+    );
+}
+
+#[test]
+fn test_void_elements() {
+    let xml = "<p><br><img src='a.png'>text<br/><br />x</p>";
+
+    let mut r = Reader::from_str(xml);
+    r.void_elements([b"br".to_vec(), b"img".to_vec()]);
+    next_eq!(
+        r, Start, b"p", Empty, b"br", Empty, b"img", Text, b"text", Empty, b"br", Empty, b"br",
+        Text, b"x", End, b"p"
+    );
+    assert_eq!(r.read_event().unwrap(), Eof);
+
+    let mut r = Reader::from_str(xml);
+    r.void_elements([b"br".to_vec()])
+        .expand_empty_elements(true);
+    next_eq!(r, Start, b"p", Start, b"br", End, b"br", Start, b"img", Text, b"text");
+
+    // Without void elements `<br>` is an ordinary start tag
+    let mut r = Reader::from_str(xml);
+    next_eq!(r, Start, b"p", Start, b"br", Start, b"img", Text, b"text");
+}
+
+/// Empty elements can be written back as they were, even if they are expanded
+#[test]
+fn test_last_start_was_empty() {
+    let xml = "<root><a x='1'/><b></b><c><d/></c></root>";
+    let mut r = Reader::from_str(xml);
+    r.expand_empty_elements(true);
+    let mut w = Writer::new(Vec::new());
+    loop {
+        match r.read_event().unwrap() {
+            Start(e) if r.last_start_was_empty() => w.write_event(Empty(e)).unwrap(),
+            End(_) if r.last_start_was_empty() => {}
+            Eof => break,
+            e => w.write_event(e).unwrap(),
+        }
+    }
+    assert_eq!(from_utf8(&w.into_inner()).unwrap(), xml);
+}
+
+#[test]
+fn test_stream_attributes() {
+    use quick_xml::events::attributes::Attr;
+
+    let mut r = Reader::from_reader(b"<a x='1'><b/><c y=\"2\" z='3'/></a>" as &[u8]);
+    r.stream_attributes(true).expand_empty_elements(true);
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        match r.read_event_into(&mut buf).unwrap() {
+            Eof => break,
+            e => events.push(e.into_owned()),
+        }
+        buf.clear();
+    }
+    let attr = |k: &str, v: &str| {
+        Attr::SingleQ(
+            Cow::Owned(k.as_bytes().to_vec()),
+            Cow::Owned(v.as_bytes().to_vec()),
+        )
+    };
+    assert_eq!(
+        events,
+        vec![
+            Start(BytesStart::borrowed_name(b"a")),
+            Attribute(attr("x", "1")),
+            AttributesEnd,
+            Start(BytesStart::borrowed_name(b"b")),
+            AttributesEnd,
+            End(BytesEnd::borrowed(b"b")),
+            Start(BytesStart::borrowed_name(b"c")),
+            Attribute(Attr::DoubleQ(
+                Cow::Borrowed(b"y" as &[u8]),
+                Cow::Borrowed(b"2" as &[u8])
+            )),
+            Attribute(attr("z", "3")),
+            AttributesEnd,
+            End(BytesEnd::borrowed(b"c")),
+            End(BytesEnd::borrowed(b"a")),
+        ]
+    );
+}
+
+#[test]
+fn test_stream_attributes_from_input() {
+    use quick_xml::events::attributes::Attr;
+
+    // Attributes borrow from the slice
+    let mut r = Reader::from_str("<a x='1' y = \"2\" />");
+    r.stream_attributes(true);
+    assert_eq!(
+        r.read_event().unwrap(),
+        Start(BytesStart::borrowed_name(b"a"))
+    );
+    match r.read_event().unwrap() {
+        Attribute(Attr::SingleQ(Cow::Borrowed(b"x"), Cow::Borrowed(b"1"))) => {}
+        e => panic!("expecting borrowed attribute, found {:?}", e),
+    }
+    match r.read_event().unwrap() {
+        Attribute(Attr::DoubleQ(Cow::Borrowed(b"y"), Cow::Borrowed(b"2"))) => {}
+        e => panic!("expecting borrowed attribute, found {:?}", e),
+    }
+    assert_eq!(r.read_event().unwrap(), AttributesEnd);
+    assert!(r.last_start_was_empty());
+    assert_eq!(r.read_event().unwrap(), End(BytesEnd::borrowed(b"a")));
+    assert_eq!(r.read_event().unwrap(), Eof);
+
+    // A reader, that returns one byte at a time, fills the buffer with
+    // one attribute only
+    let xml = "<a x='1' long='0123456789'></a>";
+    let mut r = Reader::from_reader(BufReader::with_capacity(1, xml.as_bytes()));
+    r.stream_attributes(true).max_event_size(20);
+    let mut buf = Vec::new();
+    assert_eq!(
+        r.read_event_into(&mut buf).unwrap(),
+        Start(BytesStart::borrowed_name(b"a"))
+    );
+    buf.clear();
+    assert_eq!(
+        r.read_event_into(&mut buf).unwrap(),
+        Attribute(Attr::SingleQ(Cow::Borrowed(b"x"), Cow::Borrowed(b"1")))
+    );
+    assert_eq!(buf, b" x='1'");
+    buf.clear();
+    assert_eq!(
+        r.read_event_into(&mut buf).unwrap(),
+        Attribute(Attr::SingleQ(
+            Cow::Borrowed(b"long"),
+            Cow::Borrowed(b"0123456789")
+        ))
+    );
+    buf.clear();
+    assert_eq!(r.read_event_into(&mut buf).unwrap(), AttributesEnd);
+    assert!(!r.last_start_was_empty());
+    assert_eq!(
+        r.read_event_into(&mut buf).unwrap(),
+        End(BytesEnd::borrowed(b"a"))
+    );
+
+    let xml = "<a long='0123456789abcdef'/>";
+    let mut r = Reader::from_reader(BufReader::with_capacity(1, xml.as_bytes()));
+    r.stream_attributes(true).max_event_size(20);
+    let mut buf = Vec::new();
+    r.read_event_into(&mut buf).unwrap();
+    match r.read_event_into(&mut buf) {
+        Err(quick_xml::Error::EventTooLarge {
+            limit: 20,
+            position: 2,
+        }) => {}
+        e => panic!("expecting EventTooLarge, found {:?}", e),
+    }
+    assert!(buf.len() <= 20 + 1 + "<a".len());
+}
+
+#[test]
+fn test_stream_attributes_checks() {
+    let mut r = Reader::from_str("<a x='1' y='2' x='3'/>");
+    r.stream_attributes(true).check_duplicate_attributes(true);
+    r.read_event().unwrap();
+    assert!(matches!(r.read_event().unwrap(), Attribute(_)));
+    assert!(matches!(r.read_event().unwrap(), Attribute(_)));
+    match r.read_event() {
+        Err(quick_xml::Error::DuplicateAttribute { name, position: 15 }) if name == "x" => {}
+        e => panic!("expecting DuplicateAttribute, found {:?}", e),
+    }
+
+    let mut r = Reader::from_str("<a x='1' y='2'/>");
+    r.stream_attributes(true).max_attributes(1);
+    r.read_event().unwrap();
+    assert!(matches!(r.read_event().unwrap(), Attribute(_)));
+    match r.read_event() {
+        Err(quick_xml::Error::TooManyAttributes {
+            max: 1,
+            position: 9,
+        }) => {}
+        e => panic!("expecting TooManyAttributes, found {:?}", e),
+    }
+
+    let mut r = Reader::from_str("<a x='123'/>");
+    r.stream_attributes(true).max_attribute_value_len(2);
+    r.read_event().unwrap();
+    match r.read_event() {
+        Err(quick_xml::Error::AttributeValueTooLong {
+            max: 2,
+            position: 3,
+            ..
+        }) => {}
+        e => panic!("expecting AttributeValueTooLong, found {:?}", e),
+    }
+
+    let mut r = Reader::from_str("<a x='1' y>");
+    r.stream_attributes(true);
+    r.read_event().unwrap();
+    assert!(matches!(r.read_event().unwrap(), Attribute(_)));
+    match r.read_event() {
+        Err(quick_xml::Error::InvalidAttr(AttrError::ExpectedEq(9))) => {}
+        e => panic!("expecting ExpectedEq, found {:?}", e),
+    }
+}
+
+#[test]
+fn test_stream_attributes_namespaced() {
+    use quick_xml::events::attributes::Attr;
+    use quick_xml::name::{Namespace, ResolveResult::Bound};
+
+    let mut r = Reader::from_reader(b"<p:a xmlns:p='urn:p' p:x='1'/>" as &[u8]);
+    r.stream_attributes(true);
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    match r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap() {
+        (Bound(Namespace(b"urn:p")), Start(_)) => {}
+        e => panic!("expecting bound Start, found {:?}", e),
+    }
+    let (_, e) = r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap();
+    assert!(matches!(e, Attribute(_)));
+    let (_, e) = r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap();
+    match e {
+        Attribute(Attr::SingleQ(key, _)) => assert_eq!(
+            r.attribute_namespace(QName(&key), &ns_buf).0,
+            Bound(Namespace(b"urn:p"))
+        ),
+        e => panic!("expecting Attribute, found {:?}", e),
+    }
+    let (_, e) = r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap();
+    assert_eq!(e, AttributesEnd);
+    match r.read_namespaced_event(&mut buf, &mut ns_buf).unwrap() {
+        (Bound(Namespace(b"urn:p")), End(_)) => {}
+        e => panic!("expecting bound End, found {:?}", e),
+    }
+
+    let xml = b"<a xmlns:p='urn:x' xmlns:q='urn:x' p:b='1' q:b='2'/>" as &[u8];
+    let mut r = Reader::from_reader(xml);
+    r.stream_attributes(true).check_duplicate_attributes(true);
+    match r.read_namespaced_event(&mut buf, &mut ns_buf) {
+        Err(quick_xml::Error::DuplicateAttribute { name, position: 43 }) if name == "q:b" => {}
+        e => panic!("expecting DuplicateAttribute, found {:?}", e),
+    }
+}
+
+#[test]
+fn test_attributes_sorted() {
+    let start = BytesStart::borrowed(b"tag b='1' a='2' b:a='3' B='4'", 3);
+    let keys: Vec<_> = start
+        .attributes_sorted()
+        .unwrap()
+        .into_iter()
+        .map(|a| a.key)
+        .collect();
+    assert_eq!(
+        keys,
+        vec![QName(b"B"), QName(b"a"), QName(b"b"), QName(b"b:a")]
+    );
+
+    let start = BytesStart::borrowed(b"tag a='1' a='2'", 3);
+    match start.attributes_sorted() {
+        Err(quick_xml::Error::InvalidAttr(AttrError::Duplicated(10, 4))) => {}
+        x => panic!("expecting Duplicated error, found {:?}", x),
+    }
+}
+
+/// Values that cannot be decoded are reported, but do not stop the iteration
+#[test]
+fn test_attributes_decoded() {
+    let mut r = Reader::from_bytes(b"<tag a='&amp;' b='\xFF' c='ok'/>");
+    match r.read_event().unwrap() {
+        Empty(e) => {
+            let mut attrs = e.attributes_decoded(&r, false);
+            assert_eq!(
+                attrs.next().unwrap().unwrap(),
+                (QName(b"a"), "&amp;".into())
+            );
+            assert!(matches!(
+                attrs.next(),
+                Some(Err(quick_xml::Error::NonDecodable(_)))
+            ));
+            assert_eq!(attrs.next().unwrap().unwrap(), (QName(b"c"), "ok".into()));
+            assert!(attrs.next().is_none());
+        }
+        e => panic!("Expected `Empty` event, got {:?}", e),
+    }
+}
+
+/// Entities from the reader table should be unescaped in attribute values
+#[test]
+fn test_attributes_decoded_entities() {
+    use std::collections::HashMap;
+
+    let mut entities = HashMap::new();
+    entities.insert(b"nbsp".to_vec(), "\u{A0}".as_bytes().to_vec());
+    let mut r = Reader::from_str("<tag a='1&nbsp;&lt;&nbsp;2'/>");
+    r.entities(entities);
+    assert_eq!(r.decode_and_unescape(b"&nbsp;").unwrap(), "\u{A0}");
+    match r.read_event().unwrap() {
+        Empty(e) => {
+            let mut attrs = e.attributes_decoded(&r, true);
+            assert_eq!(
+                attrs.next().unwrap().unwrap(),
+                (QName(b"a"), "1\u{A0}<\u{A0}2".into())
+            );
+            assert!(attrs.next().is_none());
+        }
+        e => panic!("Expected `Empty` event, got {:?}", e),
+    }
+}
+
+#[test]
+#[cfg(feature = "encoding")]
+fn test_decode_and_unescape_value() {
+    // "знач &amp;" in windows-1251
+    let mut reader = Reader::from_bytes(
+        b"<?xml version='1.0' encoding='windows-1251'?><tag a='\xE7\xED\xE0\xF7 &amp;'/>",
+    );
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).unwrap() {
+            Empty(e) => {
+                let attr = e.attributes().next().unwrap().unwrap();
+                assert_eq!(attr.decode_and_unescape_value(&reader).unwrap(), "знач &");
+                break;
+            }
+            Eof => panic!("Expected `Empty` event"),
+            _ => {}
+        }
+    }
+}
+
+/// Character references should be interpreted as Unicode code points, not as
+/// codes in the encoding of the document
+#[test]
+#[cfg(feature = "encoding")]
+fn test_decoder_decode_and_unescape() {
+    // "Ж &#x416;&#1046;&#xC6;" in windows-1251, where `Ж` is encoded as `C6`
+    let mut reader = Reader::from_bytes(b"<?xml version='1.0' encoding='windows-1251'?><tag/>");
+    let mut buf = Vec::new();
+    while !matches!(reader.read_event_into(&mut buf).unwrap(), Empty(_)) {}
+
+    let decoder = reader.decoder();
+    assert_eq!(decoder.encoding(), encoding_rs::WINDOWS_1251);
+    assert_eq!(
+        decoder
+            .decode_and_unescape(b"\xC6 &#x416;&#1046;&#xC6;")
+            .unwrap(),
+        "Ж ЖЖ\u{C6}"
+    );
+    assert_eq!(decoder.decode_and_unescape(b"\xC6").unwrap(), "Ж");
+    assert!(matches!(
+        decoder.decode_and_unescape(b"&unknown;"),
+        Err(quick_xml::Error::EscapeError(_))
+    ));
+}
+
+/// Explicitly set encoding should not be changed by BOM or XML declaration
+#[test]
+#[cfg(feature = "encoding")]
+fn test_explicit_encoding() {
+    // "знач" in windows-1251 after UTF-8 BOM
+    let mut reader = Reader::from_bytes(
+        b"\xEF\xBB\xBF<?xml version='1.0' encoding='utf-8'?><tag a='\xE7\xED\xE0\xF7'/>",
+    );
+    reader.encoding(encoding_rs::WINDOWS_1251);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).unwrap() {
+            Empty(e) => {
+                let attr = e.attributes().next().unwrap().unwrap();
+                assert_eq!(attr.decode_and_unescape_value(&reader).unwrap(), "знач");
+                break;
+            }
+            Eof => panic!("Expected `Empty` event"),
+            _ => {}
+        }
+    }
+    assert_eq!(reader.decoder().encoding(), encoding_rs::WINDOWS_1251);
+}
+
+/// Characters in UTF-16 documents, that contain the `0x3C` byte, should not be
+/// taken for `<`
+#[test]
+#[cfg(feature = "encoding")]
+fn test_utf16_lt_byte_in_text() {
+    // "<a>㰀㰀</a>" in UTF-16LE with BOM. `㰀` is encoded as `00 3C` and contains
+    // the byte of `<` (`3C 00`)
+    let mut reader = Reader::from_bytes(
+        b"\xFF\xFE\x3C\x00a\x00\x3E\x00\x00\x3C\x00\x3C\x3C\x00/\x00a\x00\x3E\x00",
+    );
+    let mut buf = Vec::new();
+    assert!(matches!(
+        reader.read_event_into(&mut buf).unwrap(),
+        StartText(_)
+    ));
+    assert!(matches!(
+        reader.read_event_into(&mut buf).unwrap(),
+        Start(_)
+    ));
+    match reader.read_event_into(&mut buf).unwrap() {
+        // The first `00` byte is the second byte of the preceding `>`
+        Text(e) => assert_eq!(&*e, b"\x00\x00\x3C\x00\x3C"),
+        e => panic!("Expected `Text` event, got {:?}", e),
+    }
+    assert_eq!(reader.buffer_position(), 12);
+}
+
+#[test]
+fn test_current_element() {
+    let mut r = Reader::from_str("<a><b/></a>");
+    r.expand_empty_elements(true);
+
+    next_eq!(r, Start, b"a");
+    assert_eq!(r.current_element(), Some(QName(b"a")));
+    next_eq!(r, Start, b"b");
+    assert_eq!(r.current_element(), Some(QName(b"b")));
+    next_eq!(r, End, b"b");
+    assert_eq!(r.current_element(), Some(QName(b"a")));
+    next_eq!(r, End, b"a");
+    assert_eq!(r.current_element(), None);
+
+    // Names are not tracked without `check_end_names`
+    let mut r = Reader::from_str("<a></a>");
+    r.check_end_names(false);
+
+    next_eq!(r, Start, b"a");
+    assert_eq!(r.current_element(), None);
+}
+
+/// A UTF-8 BOM is skipped by default, regardless of the `encoding` feature
+#[test]
+fn test_strip_bom() {
+    let xml = b"\xEF\xBB\xBFtext<tag/>";
+
+    let mut r = Reader::from_bytes(xml);
+    next_eq!(r, StartText, b"text", Empty, b"tag");
+
+    let mut r = Reader::from_reader(xml.as_ref());
+    r.strip_bom(false);
+    next_eq!(r, StartText, b"\xEF\xBB\xBFtext", Empty, b"tag");
+
+    // BOM is stripped only at the start of the document
+    let mut r = Reader::from_bytes(b"<tag>\xEF\xBB\xBF</tag>");
+    next_eq!(r, Start, b"tag", Text, b"\xEF\xBB\xBF", End, b"tag");
+}
+
+/// A reader, that adds one more byte to the buffer on each call to `fill_buf`
+struct Trickle<'a> {
+    data: &'a [u8],
+    available: usize,
+}
+
+impl<'a> std::io::Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.fill_buf()?.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<'a> BufRead for Trickle<'a> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.available = (self.available + 1).min(self.data.len());
+        Ok(&self.data[..self.available])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.data = &self.data[amt..];
+        self.available -= amt;
+    }
+}
+
+/// The BOM should be detected even if the reader does not buffer it at once
+#[test]
+fn test_strip_bom_short_buffer() {
+    let mut r = Reader::from_reader(Trickle {
+        data: b"\xEF\xBB\xBFtext<tag/>",
+        available: 0,
+    });
+    next_eq!(r, StartText, b"text", Empty, b"tag");
+}
+
+#[test]
+fn test_resync() {
+    let mut r = Reader::from_str("<a><b></c>skipped<d/></a>");
+
+    next_eq!(r, Start, b"a", Start, b"b");
+    assert!(r.read_event().is_err());
+    assert_eq!(r.read_event().unwrap(), Eof);
+
+    r.resync().unwrap();
+    // `buffer_position` points to the `<` of the `<d/>`
+    assert_eq!(r.buffer_position(), 17);
+    // The mismatched `</c>` closed the `<b>`
+    next_eq!(r, Empty, b"d", End, b"a");
+    assert_eq!(r.read_event().unwrap(), Eof);
+}
+
+#[test]
+fn test_resync_reset_depth() {
+    let mut r = Reader::from_reader(b"<a><b></c><d></d></a>".as_ref());
+    r.resync_reset_depth(true);
+
+    next_eq!(r, Start, b"a", Start, b"b");
+    assert!(r.read_event_into(&mut Vec::new()).is_err());
+    r.resync().unwrap();
+    next_eq!(r, Start, b"d", End, b"d");
+    // Opened `<a>` was forgotten
+    assert!(r.read_event_into(&mut Vec::new()).is_err());
+}
+
+/// When an error occurred in a text, the next markup should not be lost
+#[test]
+fn test_resync_text() {
+    let mut r = Reader::from_bytes(b"<a>\xFF</a>");
+    r.validate_utf8(true);
+
+    next_eq!(r, Start, b"a");
+    assert!(r.read_event().is_err());
+    assert_eq!(r.buffer_position(), 3);
+    r.resync().unwrap();
+    assert_eq!(r.buffer_position(), 4);
+    next_eq!(r, End, b"a");
+
+    // Does nothing without an error
+    r.resync().unwrap();
+    assert_eq!(r.read_event().unwrap(), Eof);
+}
+
+#[test]
+fn test_validate_declaration() {
+    let check = |decl: &str| {
+        let xml = format!("<root/>{}", decl);
+        let mut r = Reader::from_str(&xml);
+        r.validate_declaration(true);
+        next_eq!(r, Empty, b"root");
+        match r.read_event() {
+            Ok(Decl(_)) => Ok(()),
+            Err(quick_xml::Error::InvalidXmlDecl { reason, position }) => {
+                assert_eq!(position, 7);
+                assert_eq!(r.buffer_position(), 7);
+                Err(reason)
+            }
+            e => panic!("expecting Decl or InvalidXmlDecl, found {:?}", e),
+        }
+    };
+
+    assert_eq!(check("<?xml version='1.0'?>"), Ok(()));
+    assert_eq!(
+        check("<?xml version='1.1' encoding='utf-8' standalone='no'?>"),
+        Ok(())
+    );
+    assert_eq!(check("<?xml version='1.0' standalone='yes'?>"), Ok(()));
+
+    assert_eq!(
+        check("<?xml version='9.9'?>"),
+        Err("unsupported version `9.9`".to_string())
+    );
+    assert_eq!(
+        check("<?xml encoding='utf-8'?>"),
+        Err("`version` must be the first pseudo-attribute".to_string())
+    );
+    assert_eq!(check("<?xml ?>"), Err("`version` is missing".to_string()));
+    assert_eq!(
+        check("<?xml version='1.0' standalone='maybe'?>"),
+        Err("standalone must be `yes` or `no`, but `maybe` found".to_string())
+    );
+    assert_eq!(
+        check("<?xml version='1.0' standalone='no' encoding='utf-8'?>"),
+        Err("unexpected pseudo-attribute `encoding`".to_string())
+    );
+    assert_eq!(
+        check("<?xml version='1.0' other='1'?>"),
+        Err("unexpected pseudo-attribute `other`".to_string())
+    );
+
+    // Not checked by default
+    let mut r = Reader::from_str("<?xml version='9.9'?>");
+    assert!(matches!(r.read_event(), Ok(Decl(_))));
+}
+
+#[test]
+fn test_config() {
+    let mut r = Reader::from_str("");
+    let config = r.config();
+    assert!(config.check_end_names);
+    assert!(config.strip_bom);
+    assert!(!config.map_names);
+    assert_eq!(config.max_attributes, None);
+    assert_eq!(config.skip_events, EventMask::empty());
+
+    r.max_attributes(10)
+        .skip_events(EventMask::COMMENT)
+        .map_names(|_| None);
+    let config = r.config();
+    assert!(config.map_names);
+    assert_eq!(config.max_attributes, Some(10));
+    assert_eq!(config.skip_events, EventMask::COMMENT);
+}
+
+#[test]
+fn test_check_cdata_end_in_text() {
+    let xml = "<a>x]]>y</a>";
+    let mut r = Reader::from_str(xml);
+    next_eq!(r, Start, b"a", Text, b"x]]>y", End, b"a");
+
+    let mut r = Reader::from_reader(xml.as_bytes());
+    r.check_cdata_end_in_text(true);
+    let mut buf = Vec::new();
+    assert!(matches!(r.read_event_into(&mut buf), Ok(Start(_))));
+    match r.read_event_into(&mut buf) {
+        Err(quick_xml::Error::UnexpectedToken(token)) => assert_eq!(token, "]]>"),
+        e => panic!("Expected `UnexpectedToken` error, got {:?}", e),
+    }
+    assert_eq!(r.buffer_position(), 4);
+
+    // `]]>` inside CDATA and partial sequences are allowed
+    let mut r = Reader::from_str("<a>]] ]>]<![CDATA[]]]]><![CDATA[>]]></a>");
+    r.check_cdata_end_in_text(true);
+    next_eq!(r, Start, b"a", Text, b"]] ]>]", CData, b"]]", CData, b">", End, b"a");
+}
+
+#[test]
+fn test_max_attributes() {
+    let mut xml = String::from("<wide");
+    for i in 0..1000 {
+        xml.push_str(&format!(" a{}='{}'", i, i));
+    }
+    xml.push_str("/>");
+
+    let mut r = Reader::from_str(&xml);
+    r.max_attributes(1000);
+    next_eq!(r, Empty, b"wide");
+
+    let mut r = Reader::from_str(&xml);
+    r.max_attributes(999);
+    match r.read_event() {
+        Err(quick_xml::Error::TooManyAttributes { max, position }) => {
+            assert_eq!(max, 999);
+            assert_eq!(position, xml.find(" a999=").unwrap() + 1);
+            assert_eq!(r.buffer_position(), position);
+        }
+        e => panic!("expecting TooManyAttributes, found {:?}", e),
+    }
+    assert_eq!(r.read_event().unwrap(), Eof);
+}
+
+#[test]
+fn test_max_attribute_value_len() {
+    let long = "x".repeat(10_000);
+    let xml = format!("<a short='' long='{}'></a>", long);
+
+    let mut r = Reader::from_str(&xml);
+    r.max_attribute_value_len(10_000);
+    next_eq!(r, Start, b"a", End, b"a");
+
+    let mut r = Reader::from_reader(xml.as_bytes());
+    r.max_attribute_value_len(9_999);
+    match r.read_event_into(&mut Vec::new()) {
+        Err(quick_xml::Error::AttributeValueTooLong {
+            name,
+            max,
+            position,
+        }) => {
+            assert_eq!(name, "long");
+            assert_eq!(max, 9_999);
+            assert_eq!(position, 12);
+        }
+        e => panic!("expecting AttributeValueTooLong, found {:?}", e),
+    }
+}
+
+/// Character references to whitespaces do not separate tokens
+#[test]
+fn test_text_tokens() {
+    let text = BytesText::from_escaped_str("\r\n a&#32;b \t&lt;c&gt;\n");
+    assert_eq!(
+        text.tokens().collect::<Vec<_>>(),
+        vec![&b"a&#32;b"[..], b"&lt;c&gt;"]
+    );
+
+    let r = Reader::from_str("");
+    let tokens = text
+        .tokens_unescaped(r.decoder())
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(tokens, vec!["a b", "<c>"]);
+
+    assert_eq!(BytesText::from_escaped_str("  ").tokens().next(), None);
+}
+
+/// Errors should expose their underlying cause via `Error::source`
+#[test]
+fn test_error_source() {
+    use std::error::Error as _;
+    use std::io::{self, BufReader, Read};
+
+    struct Failing;
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "failing"))
+        }
+    }
+
+    let mut r = Reader::from_reader(BufReader::new(Failing));
+    let err = r.read_event_into(&mut Vec::new()).unwrap_err();
+    let source = err.source().expect("`Io` error should have a source");
+    let io = source.downcast_ref::<io::Error>().unwrap();
+    assert_eq!(io.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(io.to_string(), "failing");
+
+    let mut r = Reader::from_bytes(b"<a>\xFF</a>");
+    r.validate_utf8(true);
+    next_eq!(r, Start, b"a");
+    let err = r.read_event().unwrap_err();
+    let source = err
+        .source()
+        .expect("`NonDecodable` error should have a source");
+    assert!(source.downcast_ref::<std::str::Utf8Error>().is_some());
+}
+
 mod decode_with_bom_removal {
     use super::*;
     use pretty_assertions::assert_eq;
@@ -792,7 +2417,7 @@ mod decode_with_bom_removal {
         let input: &str = std::str::from_utf8(b"\xEF\xBB\xBF<?xml version=\"1.0\"?>").unwrap();
 
         let mut reader = Reader::from_str(&input);
-        reader.trim_text(true);
+        reader.trim_text(true).strip_bom(false);
 
         let mut txt = Vec::new();
         let mut buf = Vec::new();
@@ -879,3 +2504,27 @@ mod decode_with_bom_removal {
         assert!(txt.is_empty());
     }
 }
+
+mod decode_checked {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn valid() {
+        let reader = Reader::from_str("");
+        let (decoded, had_errors) = reader.decoder().decode_checked(b"text").unwrap();
+        assert_eq!(decoded, "text");
+        assert!(!had_errors);
+    }
+
+    #[test]
+    fn malformed() {
+        let reader = Reader::from_str("");
+        let (decoded, had_errors) = reader.decoder().decode_checked(b"te\xFFxt").unwrap();
+        assert_eq!(decoded, "te\u{FFFD}xt");
+        assert!(had_errors);
+
+        // Strict decoding fails on the same input
+        assert!(reader.decoder().decode(b"te\xFFxt").is_err());
+    }
+}
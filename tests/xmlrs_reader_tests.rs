@@ -473,6 +473,8 @@ fn xmlrs_display(opt_event: Result<(ResolveResult, Event)>, decoder: Decoder) ->
             format!("ProcessingInstruction(PI={})", decoder.decode(&e).unwrap())
         }
         Ok((_, Event::DocType(e))) => format!("DocType({})", decoder.decode(&e).unwrap()),
+        Ok((_, Event::Attribute(a))) => format!("{:?}", a),
+        Ok((_, Event::AttributesEnd)) => "AttributesEnd".to_string(),
         Err(e) => format!("Error: {}", e),
     }
 }